@@ -1,11 +1,19 @@
+// The original solution predates these lints and is kept as written
+#![allow(clippy::assign_op_pattern, clippy::manual_is_multiple_of, clippy::needless_return, clippy::redundant_field_names, clippy::vec_init_then_push)]
+
+use std::env;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
-struct Slope {
+mod render;
+
+pub struct Slope {
     count: u32,
     step: u32,
     down_step: u32,
     current_index: u32,
+    // Visited cells as (row, untiled column, is tree)
+    path: Vec<(usize, usize, bool)>,
 }
 
 fn build_slope(step : u32, down_step : u32) -> Slope {
@@ -13,7 +21,8 @@ fn build_slope(step : u32, down_step : u32) -> Slope {
         count: 0,
         step: step,
         down_step: down_step,
-        current_index: 0
+        current_index: 0,
+        path: Vec::new(),
     };
 
     return slope;
@@ -25,6 +34,7 @@ fn print_slope(slope: &Slope) {
 
 fn main() {
     let filename = "src/input.txt";
+    let args : Vec<String> = env::args().skip(1).collect();
 
     let file = File::open(filename).unwrap();
     let reader = BufReader::new(file);
@@ -49,6 +59,7 @@ fn main() {
             let characther = line.chars().nth(index_to_search as usize).unwrap();
 
             if characther == '#' { slope.count = slope.count + 1; }
+            slope.path.push((index_line, slope.current_index as usize, characther == '#'));
             slope.current_index = slope.current_index + slope.step;
         }
     }
@@ -60,4 +71,22 @@ fn main() {
     }
 
     println!("Number of Trees Obstructing (multiplication): {}", multiplication);
+
+    // Optional rendering of the trajectories over the tiled map
+    match args.first().map(|arg| arg.as_str()) {
+        Some("render") => {
+            let overlay = render::build_overlay(&data, &slopes);
+            print!("{}", overlay.to_terminal());
+        },
+        Some("ppm") => {
+            let output = args.get(1).map(|arg| arg.as_str()).unwrap_or("trajectory.ppm");
+            let overlay = render::build_overlay(&data, &slopes);
+            match overlay.write_ppm(output) {
+                Ok(()) => println!("Trajectory image written to '{}'", output),
+                Err(error) => println!("Could not write '{}': {}", output, error),
+            }
+        },
+        Some(other) => println!("Unknown mode '{}' (expected 'render' or 'ppm <file>')", other),
+        None => (),
+    }
 }
\ No newline at end of file
@@ -0,0 +1,127 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::Slope;
+
+// One colour per slope, reused cyclically if there are more slopes than colours
+const PALETTE : [(u8, u8, u8); 6] = [
+    (230, 57, 70),
+    (244, 162, 97),
+    (233, 196, 106),
+    (42, 157, 143),
+    (69, 123, 157),
+    (181, 101, 167),
+];
+
+// Cells where trajectories cross, so no slope hides another
+const OVERLAP_COLOUR : (u8, u8, u8) = (255, 0, 255);
+
+const PPM_CELL_SIZE : usize = 4;
+
+struct Cell {
+    tree: bool,
+    // Indexes of the slopes that visited this cell
+    slopes: Vec<usize>,
+}
+
+impl Cell {
+    // Colour of the slope visiting the cell, or the overlap colour when several do
+    fn colour(&self) -> Option<(u8, u8, u8)> {
+        match self.slopes.as_slice() {
+            [] => None,
+            [index] => Some(slope_colour(*index)),
+            _ => Some(OVERLAP_COLOUR),
+        }
+    }
+}
+
+pub struct Overlay {
+    cells: Vec<Vec<Cell>>,
+    legend: Vec<(u32, u32, u32)>,
+}
+
+fn slope_colour(index: usize) -> (u8, u8, u8) {
+    PALETTE[index % PALETTE.len()]
+}
+
+pub fn build_overlay(data: &[String], slopes: &[Slope]) -> Overlay {
+    let map_width : usize = data.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+    let trajectory_width : usize = slopes.iter()
+        .flat_map(|slope| slope.path.iter())
+        .map(|&(_, column, _)| column + 1)
+        .max().unwrap_or(0);
+    let width = map_width.max(trajectory_width);
+
+    // Tile the map horizontally as far as the furthest trajectory goes
+    let mut cells : Vec<Vec<Cell>> = data.iter().map(|line| {
+        let line_chars : Vec<char> = line.chars().collect();
+        (0..width).map(|column| Cell {
+            tree: !line_chars.is_empty() && line_chars[column % line_chars.len()] == '#',
+            slopes: Vec::new(),
+        }).collect()
+    }).collect();
+
+    for (index_slope, slope) in slopes.iter().enumerate() {
+        for &(row, column, _) in slope.path.iter() {
+            let cell = &mut cells[row][column];
+            if !cell.slopes.contains(&index_slope) { cell.slopes.push(index_slope); }
+        }
+    }
+
+    let legend = slopes.iter().map(|slope| (slope.step, slope.down_step, slope.count)).collect();
+    Overlay { cells, legend }
+}
+
+impl Overlay {
+
+    pub fn to_terminal(&self) -> String {
+        let mut output = String::new();
+        for (index, &(step, down_step, count)) in self.legend.iter().enumerate() {
+            let (red, green, blue) = slope_colour(index);
+            output.push_str(&format!("\x1b[38;2;{};{};{}m■\x1b[0m ({}, {}): {} trees in the way\n",
+                red, green, blue, step, down_step, count));
+        }
+        let (red, green, blue) = OVERLAP_COLOUR;
+        output.push_str(&format!("\x1b[38;2;{};{};{}m■\x1b[0m visited by several slopes\n", red, green, blue));
+
+        for row in self.cells.iter() {
+            for cell in row.iter() {
+                match cell.colour() {
+                    Some((red, green, blue)) => {
+                        let mark = if cell.tree { 'X' } else { 'O' };
+                        output.push_str(&format!("\x1b[1;38;2;{};{};{}m{}\x1b[0m", red, green, blue, mark));
+                    },
+                    None => output.push(if cell.tree { '#' } else { '.' }),
+                }
+            }
+            output.push('\n');
+        }
+
+        output
+    }
+
+    pub fn write_ppm(&self, filename: &str) -> io::Result<()> {
+        let height = self.cells.len();
+        let width = self.cells.first().map(|row| row.len()).unwrap_or(0);
+
+        let mut writer = BufWriter::new(File::create(filename)?);
+        write!(writer, "P6\n{} {}\n255\n", width * PPM_CELL_SIZE, height * PPM_CELL_SIZE)?;
+
+        for row in self.cells.iter() {
+            let pixels : Vec<u8> = row.iter().flat_map(|cell| {
+                let (red, green, blue) = match (cell.colour(), cell.tree) {
+                    // Trees hit by a slope are drawn in a darker shade of its colour
+                    (Some((red, green, blue)), true) => (red / 2, green / 2, blue / 2),
+                    (Some(colour), false) => colour,
+                    (None, true) => (34, 85, 34),
+                    (None, false) => (235, 235, 235),
+                };
+                [red, green, blue].repeat(PPM_CELL_SIZE)
+            }).collect();
+
+            for _ in 0..PPM_CELL_SIZE { writer.write_all(&pixels)?; }
+        }
+
+        writer.flush()
+    }
+}
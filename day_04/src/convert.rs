@@ -0,0 +1,252 @@
+use std::fs;
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::{split_parameters, Param, NO_VALUE};
+
+pub const EXTRAS_COLUMN : &str = "extras";
+
+pub struct PassportRecord {
+    // Values of the codes defined in the schema, in schema order
+    values: Vec<(String, String)>,
+    // Codes unknown to the schema (or repeated), in their original order
+    extras: Vec<(String, String)>,
+}
+
+#[derive(Clone, Copy)]
+pub enum Format {
+    Csv,
+    JsonLines,
+}
+
+impl Format {
+    pub fn from_name(name : &str) -> Option<Format> {
+        match name {
+            "csv" => Some(Format::Csv),
+            "jsonl" | "json" => Some(Format::JsonLines),
+            _ => None,
+        }
+    }
+
+    pub fn from_filename(filename : &str) -> Option<Format> {
+        Path::new(filename).extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(Format::from_name)
+    }
+}
+
+pub fn build_record(codes : &[String], params : &[Param]) -> PassportRecord {
+    let mut values : Vec<(String, String)> = Vec::new();
+    for code in codes.iter() {
+        if let Some(param) = params.iter().find(|param| &param.code == code) {
+            values.push((code.clone(), param.value.clone()));
+        }
+    }
+
+    let mut extras : Vec<(String, String)> = Vec::new();
+    for (index, param) in params.iter().enumerate() {
+        let known = codes.contains(&param.code);
+        let first = params[..index].iter().all(|previous| previous.code != param.code);
+        if !known || !first { extras.push((param.code.clone(), param.value.clone())); }
+    }
+
+    PassportRecord { values, extras }
+}
+
+// Batch format
+
+fn to_batch_params(params : &[(String, String)]) -> String {
+    params.iter()
+        .map(|(code, value)| if value == NO_VALUE { code.clone() } else { format!("{}:{}", code, value) })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn from_batch_params(line : &str) -> Vec<(String, String)> {
+    if line.is_empty() { return Vec::new(); }
+
+    split_parameters(line).into_iter()
+        .map(|param| (param.code, param.value))
+        .collect()
+}
+
+pub fn to_batch(records : &[PassportRecord]) -> String {
+    records.iter()
+        .map(|record| {
+            let all : Vec<(String, String)> = record.values.iter().chain(record.extras.iter()).cloned().collect();
+            to_batch_params(&all) + "\n"
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// CSV
+
+// Empty values are quoted, an unquoted empty cell stands for a missing field
+fn escape_csv(cell : &str) -> String {
+    if cell.is_empty() || cell.contains([',', '"', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+// Cells of a line, along with whether each one was quoted
+fn split_csv_line(line : &str, line_number : usize) -> Result<Vec<(String, bool)>, String> {
+    let mut cells : Vec<(String, bool)> = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut was_quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(characther) = chars.next() {
+        match (characther, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => { current.push('"'); chars.next(); },
+            ('"', true) => quoted = false,
+            ('"', false) if current.is_empty() => { quoted = true; was_quoted = true; },
+            (',', false) => cells.push((std::mem::take(&mut current), std::mem::take(&mut was_quoted))),
+            _ => current.push(characther),
+        }
+    }
+
+    if quoted { return Err(format!("Line {}: unterminated quoted cell", line_number)); }
+    cells.push((current, was_quoted));
+    Ok(cells)
+}
+
+pub fn to_csv(codes : &[String], records : &[PassportRecord]) -> String {
+    let mut header : Vec<String> = codes.iter().map(|code| escape_csv(code)).collect();
+    header.push(EXTRAS_COLUMN.to_string());
+    let mut output = header.join(",") + "\n";
+
+    for record in records.iter() {
+        let mut row : Vec<String> = codes.iter()
+            .map(|code| match record.values.iter().find(|(value_code, _)| value_code == code) {
+                Some((_, value)) => escape_csv(value),
+                None => String::new(),
+            })
+            .collect();
+        let extras = to_batch_params(&record.extras);
+        row.push(if extras.is_empty() { extras } else { escape_csv(&extras) });
+        output.push_str(&(row.join(",") + "\n"));
+    }
+
+    output
+}
+
+pub fn from_csv(content : &str) -> Result<Vec<PassportRecord>, String> {
+    let mut lines = content.lines().enumerate().filter(|(_, line)| !line.is_empty());
+    let header : Vec<String> = match lines.next() {
+        Some((index, line)) => split_csv_line(line, index + 1)?.into_iter().map(|(cell, _)| cell).collect(),
+        None => return Ok(Vec::new()),
+    };
+
+    let mut records : Vec<PassportRecord> = Vec::new();
+    for (index, line) in lines {
+        let cells = split_csv_line(line, index + 1)?;
+        if cells.len() != header.len() {
+            return Err(format!("Line {}: expected {} cells but found {}", index + 1, header.len(), cells.len()));
+        }
+
+        let mut record = PassportRecord { values: Vec::new(), extras: Vec::new() };
+        for (column, (cell, quoted)) in header.iter().zip(cells) {
+            if column == EXTRAS_COLUMN { record.extras = from_batch_params(&cell); }
+            else if quoted || !cell.is_empty() { record.values.push((column.clone(), cell)); }
+        }
+        records.push(record);
+    }
+
+    Ok(records)
+}
+
+// JSON Lines
+
+pub fn to_jsonl(records : &[PassportRecord]) -> String {
+    records.iter()
+        .map(|record| {
+            let mut object : Map<String, Value> = record.values.iter()
+                .map(|(code, value)| (code.clone(), Value::String(value.clone())))
+                .collect();
+            // Extras may repeat a code, so they are kept as '[code, value]' pairs rather than an object
+            if !record.extras.is_empty() {
+                let extras : Vec<Value> = record.extras.iter()
+                    .map(|(code, value)| Value::Array(vec![Value::String(code.clone()), Value::String(value.clone())]))
+                    .collect();
+                object.insert(EXTRAS_COLUMN.to_string(), Value::Array(extras));
+            }
+            Value::Object(object).to_string() + "\n"
+        })
+        .collect()
+}
+
+fn json_string_pairs(object : Map<String, Value>, line_number : usize) -> Result<Vec<(String, String)>, String> {
+    let mut pairs : Vec<(String, String)> = Vec::new();
+    for (code, value) in object.into_iter() {
+        match value {
+            Value::String(value) => pairs.push((code, value)),
+            Value::Null => (),
+            other => return Err(format!("Line {}: value of '{}' should be a string, found {}", line_number, code, other)),
+        }
+    }
+
+    Ok(pairs)
+}
+
+fn json_extras(extras : Vec<Value>, line_number : usize) -> Result<Vec<(String, String)>, String> {
+    extras.into_iter()
+        .map(|pair| match pair {
+            Value::Array(pair) => match pair.as_slice() {
+                [Value::String(code), Value::String(value)] => Ok((code.clone(), value.clone())),
+                _ => Err(format!("Line {}: '{}' pairs should hold two strings", line_number, EXTRAS_COLUMN)),
+            },
+            _ => Err(format!("Line {}: '{}' should hold '[code, value]' pairs", line_number, EXTRAS_COLUMN)),
+        })
+        .collect()
+}
+
+pub fn from_jsonl(content : &str) -> Result<Vec<PassportRecord>, String> {
+    let mut records : Vec<PassportRecord> = Vec::new();
+
+    for (index, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let mut object = match serde_json::from_str::<Value>(line) {
+            Ok(Value::Object(object)) => object,
+            Ok(_) => return Err(format!("Line {}: expected a JSON object", index + 1)),
+            Err(error) => return Err(format!("Line {}: {}", index + 1, error)),
+        };
+
+        let extras = match object.remove(EXTRAS_COLUMN) {
+            Some(Value::Array(extras)) => json_extras(extras, index + 1)?,
+            Some(Value::Null) | None => Vec::new(),
+            Some(_) => return Err(format!("Line {}: '{}' should be an array", index + 1, EXTRAS_COLUMN)),
+        };
+        let values = json_string_pairs(object, index + 1)?;
+
+        records.push(PassportRecord { values, extras });
+    }
+
+    Ok(records)
+}
+
+// Files
+
+pub fn export(filename : &str, format : Format, codes : &[String], records : &[PassportRecord]) -> Result<(), String> {
+    let content = match format {
+        Format::Csv => to_csv(codes, records),
+        Format::JsonLines => to_jsonl(records),
+    };
+
+    fs::write(filename, content).map_err(|error| format!("Could not write '{}': {}", filename, error))
+}
+
+pub fn import(filename : &str) -> Result<Vec<PassportRecord>, String> {
+    let format = Format::from_filename(filename)
+        .ok_or(format!("Could not infer the format of '{}' (expected '.csv' or '.jsonl')", filename))?;
+    let content = fs::read_to_string(filename)
+        .map_err(|error| format!("Could not read '{}': {}", filename, error))?;
+
+    match format {
+        Format::Csv => from_csv(&content),
+        Format::JsonLines => from_jsonl(&content),
+    }
+}
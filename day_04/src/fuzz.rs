@@ -0,0 +1,126 @@
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::{build_field_from_template, Field};
+
+const DEFAULT_SEED : u64 = 0x2020_0004;
+const MAX_VALUE_CHARS : u64 = 12;
+const MAX_REPORTED_PANICS : usize = 10;
+
+// Hand-picked values around the edges the validators have to deal with
+const CORPUS : [&str; 16] = [
+    "", "5", "cm", "in", "#", "hcl:", "#123abc", "#123abcd", "150cm", "76in",
+    "é", "1é", "ñcm", "#🦀🦀🦀🦀🦀🦀", "99999999999999999999999cm", "-5in",
+];
+
+// Fragments mixed into the random values so that they often get close to valid ones
+const FRAGMENTS : [&str; 10] = ["#", "cm", "in", "19", "20", "abc", "amb", "é", "🦀", " "];
+
+// Small xorshift generator so the harness needs no extra dependency
+struct Generator {
+    state: u64,
+}
+
+impl Generator {
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    fn below(&mut self, upper : u64) -> u64 {
+        self.next_u64() % upper
+    }
+
+    fn next_value(&mut self) -> String {
+        let mut value = String::new();
+        for _ in 0..self.below(MAX_VALUE_CHARS + 1) {
+            match self.below(4) {
+                0 => value.push((b'0' + self.below(10) as u8) as char),
+                1 => value.push((b'a' + self.below(26) as u8) as char),
+                2 => value.push_str(FRAGMENTS[self.below(FRAGMENTS.len() as u64) as usize]),
+                _ => value.push(char::from_u32(self.below(0x11_0000) as u32).unwrap_or('\u{fffd}')),
+            }
+        }
+
+        value
+    }
+}
+
+pub struct FuzzSummary {
+    pub checked: usize,
+    pub panics: Vec<(String, String)>,
+}
+
+pub fn run(fields : &[Field], iterations : usize, seed : Option<u64>) -> FuzzSummary {
+    // A zero state would make xorshift stuck at zero
+    let mut generator = Generator { state: seed.unwrap_or(DEFAULT_SEED).max(1) };
+    let values : Vec<String> = CORPUS.iter().map(|value| value.to_string())
+        .chain((0..iterations).map(|_| generator.next_value()))
+        .collect();
+
+    let mut summary = FuzzSummary { checked: 0, panics: Vec::new() };
+
+    // Panics are expected to be collected, not printed
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+
+    for value in values.iter() {
+        for field in fields.iter() {
+            let new_field = build_field_from_template(field, value.clone());
+            let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                new_field.validate();
+                new_field.validator.describe();
+            }));
+
+            summary.checked += 1;
+            if result.is_err() { summary.panics.push((field.field_code.clone(), value.clone())); }
+        }
+    }
+
+    panic::set_hook(previous_hook);
+    summary
+}
+
+pub fn print_summary(summary : &FuzzSummary) {
+    println!("Fuzzed {} field values: {} panic(s)", summary.checked, summary.panics.len());
+    for (code, value) in summary.panics.iter().take(MAX_REPORTED_PANICS) {
+        println!("\tPanic validating {}: {:?}", code, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::schema::Schema;
+
+    // `run` swaps the global panic hook, so the tests must not overlap
+    static SERIAL : Mutex<()> = Mutex::new(());
+
+    // Inputs that used to crash the validators, written as they appear in a batch file
+    const REGRESSIONS : [&str; 6] = ["hgt:5", "hgt:", "hcl:", "hcl:#", "hgt:1é", "hcl:#é1234"];
+
+    #[test]
+    fn regressions_get_a_verdict() {
+        let _serial = SERIAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let fields = Schema::default_schema().build_fields();
+
+        for parameter in REGRESSIONS.iter() {
+            let mut parameter_split = parameter.split(':');
+            let (code, value) = (parameter_split.next().unwrap(), parameter_split.next().unwrap());
+            let field = fields.iter().find(|field| field.field_code == code).unwrap();
+            assert!(!build_field_from_template(field, value.to_string()).validate(), "{} should be invalid", parameter);
+        }
+    }
+
+    #[test]
+    fn default_schema_never_panics() {
+        let _serial = SERIAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let summary = run(&Schema::default_schema().build_fields(), 10_000, None);
+
+        assert_eq!(summary.checked, (CORPUS.len() + 10_000) * Schema::default_schema().build_fields().len());
+        assert!(summary.panics.is_empty(), "Panics: {:?}", summary.panics);
+    }
+}
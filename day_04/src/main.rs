@@ -1,18 +1,30 @@
-use std::fs::File;
+use std::fs::{self, File};
+use std::env;
 use std::io::{BufRead, BufReader};
-use std::num::ParseIntError;
 
-struct Field {
+mod convert;
+mod fuzz;
+mod report;
+mod schema;
+
+use report::{FieldIssue, IssueKind, PassportReport};
+use schema::{Schema, Validator};
+
+const DEFAULT_FUZZ_ITERATIONS : usize = 100_000;
+// Value of a parameter written without ':'
+const NO_VALUE : &str = "No element there (index 0)";
+
+pub struct Field {
     field_code: String,
     field_name: String,
     value: String,
     optional: bool,
-    callback: fn(String) -> bool,
+    validator: Validator,
 }
 
 impl Field {
     fn validate(&self) -> bool {
-        return (self.callback)(self.value.clone());
+        self.validator.validate(&self.value)
     }
 }
 
@@ -21,195 +33,174 @@ struct Param {
     value: String,
 }
 
-fn build_field(field_code : &str, field_name: &str, optional : bool, callback : fn(String) -> bool) -> Field {
-    let new_field = Field {
+fn build_field(field_code : &str, field_name: &str, optional : bool, validator : Validator) -> Field {
+    Field {
         field_code: field_code.to_string(),
         field_name: field_name.to_string(),
         value: "None".to_string(),
-        optional: optional,
-        callback: callback,
-    };
-
-    return new_field;
+        optional,
+        validator,
+    }
 }
 
 fn build_field_from_template(field_mockup : &Field, value : String) -> Field {
-    let new_field = Field {
+    Field {
         field_code: field_mockup.field_code.clone(),
         field_name: field_mockup.field_name.clone(),
-        value: value,
+        value,
         optional: field_mockup.optional,
-        callback: field_mockup.callback,
-    };
-
-    return new_field;
+        validator: field_mockup.validator.clone(),
+    }
 }
 
 fn build_param(code : String, value : String) -> Param {
-    let new_param = Param {
-        code: code,
-        value: value,
-    };
+    Param {
+        code,
+        value,
+    }
+}
 
-    return new_param;
+// Splits a batch line into its parameters, the converters use it too so they see the same fields
+fn split_parameters(line : &str) -> Vec<Param> {
+    let information = line.split(" ");
+    information.map(|parameter| {
+        let mut parameter_split = parameter.split(":");
+        let parameter_code = parameter_split.next().unwrap_or(NO_VALUE).to_string();
+        let parameter_value = parameter_split.next().unwrap_or(NO_VALUE).to_string();
+
+        build_param(parameter_code, parameter_value)
+    }).collect()
 }
 
 fn main() {
     let filename = "src/input.txt";
 
+    let args : Vec<String> = env::args().skip(1).collect();
+
+    // Importing converts a CSV/JSON Lines file back into the batch format, without any input
+    if let Some(index) = args.iter().position(|arg| arg == "--import") {
+        let import_filename = args.get(index + 1).expect("Missing file after '--import'");
+        let batch = convert::to_batch(&convert::import(import_filename).unwrap_or_else(|error| panic!("{}", error)));
+        match args.get(index + 2).filter(|output| !output.starts_with("--")) {
+            Some(output) => fs::write(output, batch).unwrap_or_else(|error| panic!("Could not write '{}': {}", output, error)),
+            None => print!("{}", batch),
+        }
+        return;
+    }
+
     let file = File::open(filename).unwrap();
     let reader = BufReader::new(file);
 
     let data : Vec<String> = reader.lines()
         .collect::<Result<_, _>>().unwrap();
 
-    let mut fields : Vec<Field> = Vec::new();
-    fields.push(build_field("byr", "Birth Year", false, validate_byr));
-    fields.push(build_field("iyr", "Issue Year", false, validate_iyr));
-    fields.push(build_field("eyr", "Expiration Year", false, validate_eyr));
-    fields.push(build_field("hgt", "Height", false, validate_hgt));
-    fields.push(build_field("hcl", "Hair Color", false, validate_hcl));
-    fields.push(build_field("ecl", "Eye Color", false, validate_ecl));
-    fields.push(build_field("pid", "Passport ID", false, validate_pid));
-    fields.push(build_field("cid", "Country ID", true, validate_cid));
+    // Field definitions come from a schema file, defaulting to the built-in one
+    let schema : Schema = match args.iter().position(|arg| arg == "--schema") {
+        Some(index) => match args.get(index + 1) {
+            Some(schema_filename) => Schema::load(schema_filename).unwrap_or_else(|error| panic!("{}", error)),
+            None => panic!("Missing file after '--schema'"),
+        },
+        None => Schema::default_schema(),
+    };
+    let fields : Vec<Field> = schema.build_fields();
+
+    // Fuzzing mode feeds arbitrary values through every field validator instead of the input
+    if let Some(index) = args.iter().position(|arg| arg == "--fuzz") {
+        let iterations : usize = args.get(index + 1).and_then(|value| value.parse().ok()).unwrap_or(DEFAULT_FUZZ_ITERATIONS);
+        let seed : Option<u64> = args.iter().position(|arg| arg == "--seed")
+            .and_then(|index_seed| args.get(index_seed + 1))
+            .and_then(|value| value.parse().ok());
+
+        fuzz::print_summary(&fuzz::run(&fields, iterations, seed));
+        return;
+    }
 
     let mut passports : Vec<Vec<Field>> = Vec::new();
+    let mut reports : Vec<PassportReport> = Vec::new();
+    let mut batch : Vec<Vec<Param>> = Vec::new();
     let mut current_passport : Vec<Param> = Vec::new();
-    for line in data.iter() {
+    let mut record_start : usize = 1;
+    for (index_line, line) in data.iter().enumerate() {
         let char_count = line.chars().count();
         if char_count == 0 {
-            add_check_passport(&mut passports, &mut fields, &mut current_passport);
-            current_passport = Vec::new();
+            add_check_passport(&mut passports, &mut reports, &fields, &current_passport, (record_start, index_line));
+            batch.push(std::mem::take(&mut current_passport));
+            record_start = index_line + 2;
             continue;
         }
 
-        let information = line.split(" ");
-        for parameter in information {
-            let mut parameter_split = parameter.split(":");
-            let parameter_code = parameter_split.nth(0).unwrap_or("No element there (index 0)").to_string();
-            let parameter_value = parameter_split.nth(0).unwrap_or("No element there (index 0)").to_string();
-
-            current_passport.push(build_param(parameter_code, parameter_value));
-        }
+        current_passport.extend(split_parameters(line));
     }
 
-    add_check_passport(&mut passports, &mut fields, &mut current_passport);
+    add_check_passport(&mut passports, &mut reports, &fields, &current_passport, (record_start, data.len()));
+    batch.push(current_passport);
 
     println!("Number of valid Passports: {}", passports.len());
+
+    // Optional per-passport report of every failing field
+    match args.iter().position(|arg| arg == "--report").map(|index| args.get(index + 1).map(|format| format.as_str()).filter(|format| !format.starts_with("--"))) {
+        Some(Some("table")) | Some(None) => print!("{}", report::to_table(&reports)),
+        Some(Some("json")) => println!("{}", report::to_json(&reports)),
+        Some(Some(other)) => println!("Unknown report format '{}' (expected 'table' or 'json')", other),
+        None => (),
+    }
+
+    // Optional export of every record to CSV or JSON Lines
+    if let Some(index) = args.iter().position(|arg| arg == "--export") {
+        let format = args.get(index + 1).and_then(|name| convert::Format::from_name(name))
+            .expect("Expected 'csv' or 'jsonl' after '--export'");
+        let output = args.get(index + 2).expect("Missing file after the export format");
+
+        let codes : Vec<String> = fields.iter().map(|field| field.field_code.clone()).collect();
+        let records : Vec<convert::PassportRecord> = batch.iter()
+            .filter(|params| !params.is_empty())
+            .map(|params| convert::build_record(&codes, params))
+            .collect();
+
+        match convert::export(output, format, &codes, &records) {
+            Ok(()) => println!("Exported {} records to '{}'", records.len(), output),
+            Err(error) => println!("{}", error),
+        }
+    }
 }
 
-fn add_check_passport(passports : &mut Vec<Vec<Field>>, fields : &mut Vec<Field>, current_passport : &mut Vec<Param>) {
+fn add_check_passport(passports : &mut Vec<Vec<Field>>, reports : &mut Vec<PassportReport>, fields : &[Field], current_passport : &[Param], lines : (usize, usize)) {
+    // Consecutive blank lines do not delimit an actual record
+    if current_passport.is_empty() { return; }
+
     let mut passport : Vec<Field> = Vec::new();
+    let mut issues : Vec<FieldIssue> = Vec::new();
 
     for field in fields.iter() {
         let information = current_passport.iter().find(|info| info.code == field.field_code);
         match information {
             Some(information_some) => {
                 let new_field = build_field_from_template(field, information_some.value.clone());
-                if !new_field.validate() { return; };
+                if !new_field.validate() {
+                    let reason = format!("value '{}' rejected, {}", new_field.value, new_field.validator.describe());
+                    issues.push(report::build_issue(IssueKind::Invalid, &field.field_code, reason));
+                    continue;
+                }
 
                 passport.push(new_field);
             },
             None => {
                 if !field.optional {
-                    //println!("Non-optional information not given - {}", field.field_name);
-                    return;
+                    let reason = format!("non-optional information not given - {}", field.field_name);
+                    issues.push(report::build_issue(IssueKind::Missing, &field.field_code, reason));
                 }
             }
         }
     }
 
-    passports.push(passport);
-}
-
-
-// Support Functions
-fn parse_i32(value : String) -> Result<i32, ParseIntError> {
-    return value.parse();
-}
-
-fn between_values(value : i32, lower : i32, upper : i32) -> bool {
-    return value >= lower && value <= upper;
-}
-
-
-// Support Validation Functions
-fn validate_byr(value : String) -> bool {
-    let value_parsed : i32 = match parse_i32(value) {
-        Ok(number) => number,
-        Err(_e) => return false,
-    };
-
-    return between_values(value_parsed, 1920, 2002);
-}
-
-fn validate_iyr(value : String) -> bool {
-    let value_parsed : i32 = match parse_i32(value)  {
-        Ok(number) => number,
-        Err(_e) => return false,
-    };
-
-    return between_values(value_parsed, 2010, 2020);
-}
-
-fn validate_eyr(value : String) -> bool {
-    let value_parsed : i32 = match parse_i32(value) {
-        Ok(number) => number,
-        Err(_e) => return false,
-    };
-    
-    return between_values(value_parsed, 2020, 2030);
-}
-
-fn validate_hgt(value : String) -> bool {
-    let len_value : usize = value.chars().count();
-    let scale : String = value[len_value - 2 ..].to_string();
-    let value_parsed : i32 = match parse_i32(value[.. len_value - 2].to_string()) {
-        Ok(number) => number,
-        Err(_e) => return false,
-    };
-
-    if scale == "cm" {return between_values(value_parsed, 150, 193)}
-    else if scale == "in" {return between_values(value_parsed, 59, 76)}
-    else { return false; }
-}
-
-fn validate_hcl(value : String) -> bool {
-    let initial_characther : String = "#".to_string();
-    let number_characthers : u32 = 6;
-
-    let mut iter_chars = value.chars();
-
-    if iter_chars.nth(0).unwrap().to_string() != initial_characther { return false; }
-    if iter_chars.clone().count() as u32 != number_characthers { return false; }
-    for characther in iter_chars {
-        if !((characther >= 'a' && characther <= 'f') || (characther >= '0' && characther <= '9')) {
-            return false;
-        }
-    }
-
-    return true;
-}
-
-fn validate_ecl(value : String) -> bool {
-    let available_colors : Vec<&str> = vec!["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
-    return available_colors.iter().any(|&x| x == value);
-}
-
-fn validate_pid(value : String) -> bool {
-    let number_characthers : u32 = 9;
+    for param in current_passport.iter() {
+        if fields.iter().any(|field| field.field_code == param.code) { continue; }
 
-    let iter_chars = value.chars();
-    if iter_chars.clone().count() as u32 != number_characthers { return false; }
-    for characther in iter_chars {
-        if !(characther >= '0' && characther <= '9') {
-            return false;
-        }
+        let reason = format!("code not defined in the schema (value '{}')", param.value);
+        issues.push(report::build_issue(IssueKind::Unknown, &param.code, reason));
     }
 
-    return true;
+    let new_report = report::build_report(lines, issues);
+    if new_report.is_valid() { passports.push(passport); }
+    reports.push(new_report);
 }
-
-fn validate_cid(_value : String) -> bool {
-    return true;
-}
\ No newline at end of file
@@ -0,0 +1,88 @@
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    Missing,
+    Invalid,
+    // Unknown codes are reported but do not make a passport invalid
+    Unknown,
+}
+
+#[derive(Serialize)]
+pub struct FieldIssue {
+    code: String,
+    kind: IssueKind,
+    reason: String,
+}
+
+#[derive(Serialize)]
+pub struct PassportReport {
+    first_line: usize,
+    last_line: usize,
+    valid: bool,
+    issues: Vec<FieldIssue>,
+}
+
+pub fn build_issue(kind : IssueKind, code : &str, reason : String) -> FieldIssue {
+    FieldIssue {
+        code: code.to_string(),
+        kind,
+        reason,
+    }
+}
+
+pub fn build_report(lines : (usize, usize), issues : Vec<FieldIssue>) -> PassportReport {
+    PassportReport {
+        first_line: lines.0,
+        last_line: lines.1,
+        valid: issues.iter().all(|issue| issue.kind == IssueKind::Unknown),
+        issues,
+    }
+}
+
+impl PassportReport {
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+}
+
+impl IssueKind {
+    fn label(&self) -> &'static str {
+        match self {
+            IssueKind::Missing => "missing",
+            IssueKind::Invalid => "invalid",
+            IssueKind::Unknown => "unknown",
+        }
+    }
+}
+
+pub fn to_table(reports : &[PassportReport]) -> String {
+    let mut output = format!("{:<6} {:<11} {:<8} {:<5} {:<8} {}\n", "Record", "Lines", "Status", "Code", "Issue", "Reason");
+
+    for (index, report) in reports.iter().enumerate() {
+        let lines = format!("{}-{}", report.first_line, report.last_line);
+        let status = if report.valid { "valid" } else { "invalid" };
+
+        if report.issues.is_empty() {
+            output.push_str(&format!("{:<6} {:<11} {}\n", index + 1, lines, status));
+            continue;
+        }
+
+        for (index_issue, issue) in report.issues.iter().enumerate() {
+            // Record columns are only written on the first row of each record
+            if index_issue == 0 {
+                output.push_str(&format!("{:<6} {:<11} {:<8} ", index + 1, lines, status));
+            } else {
+                output.push_str(&format!("{:<6} {:<11} {:<8} ", "", "", ""));
+            }
+            output.push_str(&format!("{:<5} {:<8} {}\n", issue.code, issue.kind.label(), issue.reason));
+        }
+    }
+
+    output
+}
+
+pub fn to_json(reports : &[PassportReport]) -> String {
+    serde_json::to_string_pretty(reports).expect("Reports should always serialize")
+}
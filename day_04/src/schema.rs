@@ -0,0 +1,132 @@
+use std::convert::TryFrom;
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+use serde::Deserialize;
+
+use crate::convert::EXTRAS_COLUMN;
+use crate::{build_field, Field};
+
+const DEFAULT_SCHEMA : &str = include_str!("schema.toml");
+
+// Regex compiled once when the schema is loaded
+#[derive(Clone, Debug, Deserialize)]
+#[serde(try_from = "String")]
+pub struct Pattern(Regex);
+
+impl TryFrom<String> for Pattern {
+    type Error = regex::Error;
+
+    fn try_from(pattern: String) -> Result<Self, Self::Error> {
+        Regex::new(&pattern).map(Pattern)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct UnitRange {
+    unit: String,
+    min: i64,
+    max: i64,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Validator {
+    IntRange { min: i64, max: i64 },
+    OneOf { values: Vec<String> },
+    Regex { pattern: Pattern },
+    Units { units: Vec<UnitRange> },
+    Any,
+}
+
+impl Validator {
+    pub fn validate(&self, value: &str) -> bool {
+        match self {
+            Validator::IntRange { min, max } => match value.parse::<i64>() {
+                Ok(number) => (*min..=*max).contains(&number),
+                Err(_) => false,
+            },
+            Validator::OneOf { values } => values.iter().any(|option| option == value),
+            Validator::Regex { pattern } => pattern.0.is_match(value),
+            Validator::Units { units } => units.iter().any(|range| {
+                match value.strip_suffix(range.unit.as_str()).map(|number| number.parse::<i64>()) {
+                    Some(Ok(number)) => (range.min..=range.max).contains(&number),
+                    _ => false,
+                }
+            }),
+            Validator::Any => true,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Validator::IntRange { min, max } => format!("expected an integer between {} and {}", min, max),
+            Validator::OneOf { values } => format!("expected one of [{}]", values.join(", ")),
+            Validator::Regex { pattern } => format!("expected a match for '{}'", pattern.0.as_str()),
+            Validator::Units { units } => {
+                let options : Vec<String> = units.iter()
+                    .map(|range| format!("{}-{}{}", range.min, range.max, range.unit))
+                    .collect();
+                format!("expected a measure within [{}]", options.join(", "))
+            },
+            Validator::Any => "any value is accepted".to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct FieldSchema {
+    code: String,
+    name: String,
+    #[serde(default)]
+    optional: bool,
+    validator: Validator,
+}
+
+#[derive(Deserialize)]
+pub struct Schema {
+    fields: Vec<FieldSchema>,
+}
+
+impl Schema {
+
+    pub fn default_schema() -> Schema {
+        Schema::from_toml(DEFAULT_SCHEMA).expect("Default schema should be valid")
+    }
+
+    pub fn load(filename: &str) -> Result<Schema, String> {
+        let content = fs::read_to_string(filename)
+            .map_err(|error| format!("Could not read schema '{}': {}", filename, error))?;
+
+        match Path::new(filename).extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Schema::from_json(&content),
+            Some("toml") => Schema::from_toml(&content),
+            _ => Err(format!("Schema '{}' should have a '.toml' or '.json' extension", filename)),
+        }
+    }
+
+    pub fn from_toml(content: &str) -> Result<Schema, String> {
+        toml::from_str(content).map_err(|error| format!("Invalid TOML schema: {}", error))
+            .and_then(Schema::check)
+    }
+
+    pub fn from_json(content: &str) -> Result<Schema, String> {
+        serde_json::from_str(content).map_err(|error| format!("Invalid JSON schema: {}", error))
+            .and_then(Schema::check)
+    }
+
+    // The converters keep unknown codes in their own column, a field can't take its name
+    fn check(self) -> Result<Schema, String> {
+        match self.fields.iter().find(|field| field.code == EXTRAS_COLUMN) {
+            Some(field) => Err(format!("Field code '{}' is reserved for the converted extras", field.code)),
+            None => Ok(self),
+        }
+    }
+
+    pub fn build_fields(&self) -> Vec<Field> {
+        self.fields.iter()
+            .map(|field| build_field(&field.code, &field.name, field.optional, field.validator.clone()))
+            .collect()
+    }
+}
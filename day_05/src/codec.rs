@@ -0,0 +1,112 @@
+use std::fmt;
+
+use crate::{build_seat, Seat};
+
+// Seat ids have to fit in the i32 used by `Seat`
+const MAX_TOTAL_BITS : u32 = 31;
+
+#[derive(Debug, PartialEq)]
+pub enum PassError {
+    InvalidGeometry { row_bits: u32, collumn_bits: u32 },
+    WrongLength { expected: usize, found: usize },
+    InvalidCharacter { index: usize, found: char, expected: (char, char) },
+    SeatOutOfRange { row: u32, collumn: u32 },
+}
+
+impl fmt::Display for PassError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PassError::InvalidGeometry { row_bits, collumn_bits } =>
+                write!(f, "Geometry of {} row bits and {} collumn bits is not supported (at most {} bits in total)", row_bits, collumn_bits, MAX_TOTAL_BITS),
+            PassError::WrongLength { expected, found } =>
+                write!(f, "Pass should have {} characters but has {}", expected, found),
+            PassError::InvalidCharacter { index, found, expected } =>
+                write!(f, "Character '{}' at position {} should be '{}' or '{}'", found, index, expected.0, expected.1),
+            PassError::SeatOutOfRange { row, collumn } =>
+                write!(f, "Seat at row {} and collumn {} does not exist in this aircraft", row, collumn),
+        }
+    }
+}
+
+pub struct Geometry {
+    row_bits: u32,
+    collumn_bits: u32,
+    // Characters for the (lower, upper) half of each partition
+    row_chars: (char, char),
+    collumn_chars: (char, char),
+}
+
+impl Geometry {
+    pub fn new(row_bits : u32, collumn_bits : u32) -> Result<Geometry, PassError> {
+        if row_bits.checked_add(collumn_bits).is_none_or(|total| total > MAX_TOTAL_BITS) {
+            return Err(PassError::InvalidGeometry { row_bits, collumn_bits });
+        }
+
+        Ok(Geometry {
+            row_bits,
+            collumn_bits,
+            row_chars: ('F', 'B'),
+            collumn_chars: ('L', 'R'),
+        })
+    }
+
+    pub fn parse(value : &str) -> Result<Geometry, String> {
+        let (rows, collumns) = value.split_once('x')
+            .ok_or(format!("Geometry '{}' should look like '<row bits>x<collumn bits>'", value))?;
+        let row_bits : u32 = rows.parse().map_err(|_| format!("Invalid row bits '{}'", rows))?;
+        let collumn_bits : u32 = collumns.parse().map_err(|_| format!("Invalid collumn bits '{}'", collumns))?;
+
+        Geometry::new(row_bits, collumn_bits).map_err(|error| error.to_string())
+    }
+
+    pub fn rows(&self) -> u32 {
+        1 << self.row_bits
+    }
+
+    pub fn collumns(&self) -> u32 {
+        1 << self.collumn_bits
+    }
+
+    pub fn pass_length(&self) -> usize {
+        (self.row_bits + self.collumn_bits) as usize
+    }
+
+    pub fn seat_id(&self, row : u32, collumn : u32) -> u32 {
+        (row << self.collumn_bits) | collumn
+    }
+
+    pub fn decode(&self, pass : &str) -> Result<Seat, PassError> {
+        let characters : Vec<char> = pass.chars().collect();
+        if characters.len() != self.pass_length() {
+            return Err(PassError::WrongLength { expected: self.pass_length(), found: characters.len() });
+        }
+
+        // Each character is a single bit, most significant first
+        let mut value : u32 = 0;
+        for (index, &character) in characters.iter().enumerate() {
+            let expected = if index < self.row_bits as usize { self.row_chars } else { self.collumn_chars };
+            let bit = if character == expected.0 { 0 }
+                else if character == expected.1 { 1 }
+                else { return Err(PassError::InvalidCharacter { index, found: character, expected }); };
+
+            value = (value << 1) | bit;
+        }
+
+        let row = value >> self.collumn_bits;
+        let collumn = value & (self.collumns() - 1);
+        Ok(build_seat(self, row, collumn))
+    }
+
+    pub fn encode(&self, seat : &Seat) -> Result<String, PassError> {
+        if seat.row >= self.rows() || seat.collumn >= self.collumns() {
+            return Err(PassError::SeatOutOfRange { row: seat.row, collumn: seat.collumn });
+        }
+
+        let row_part = (0..self.row_bits).rev()
+            .map(|bit| if seat.row >> bit & 1 == 0 { self.row_chars.0 } else { self.row_chars.1 });
+        let collumn_part = (0..self.collumn_bits).rev()
+            .map(|bit| if seat.collumn >> bit & 1 == 0 { self.collumn_chars.0 } else { self.collumn_chars.1 });
+
+        Ok(row_part.chain(collumn_part).collect())
+    }
+}
@@ -1,32 +1,31 @@
+use std::collections::HashSet;
+use std::env;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
-struct Seat {
+mod codec;
+mod occupancy;
+
+use codec::Geometry;
+use occupancy::Occupancy;
+
+pub struct Seat {
     row : u32,
     collumn : u32,
     id : i32,
 }
 
-impl Seat {
-    fn compute_id(&mut self) {
-        self.id = formula_id(self.row, self.collumn) as i32;
+fn build_seat(geometry : &Geometry, row : u32, collumn : u32) -> Seat {
+    Seat {
+        row,
+        collumn,
+        id : geometry.seat_id(row, collumn) as i32,
     }
 }
 
-fn build_seat(row : u32, collumn : u32) -> Seat {
-    let mut new_seat = Seat {
-        row : row,
-        collumn : collumn,
-        id : -1,
-    };
-
-    new_seat.compute_id();
-    return new_seat;
-}
-
-const MAX_ROW : u32= 127;
-const MAX_COLLUMN : u32 = 7;
-const DIVISION_INDEX : usize = 7;
+// Default aircraft: 128 rows (7 bits) of 8 collumns (3 bits)
+const DEFAULT_ROW_BITS : u32 = 7;
+const DEFAULT_COLLUMN_BITS : u32 = 3;
 
 fn main() {
     let filename = "src/input.txt";
@@ -37,94 +36,86 @@ fn main() {
     let data : Vec<String> = reader.lines()
         .collect::<Result<_, _>>().unwrap();
 
-    let mut seen_seats : Vec<Seat> = Vec::new();
-    for line in data.iter() {
-        let (row_code, collumn_code) = line.split_at(DIVISION_INDEX);
+    let args : Vec<String> = env::args().skip(1).collect();
+    let geometry : Geometry = match args.iter().position(|arg| arg == "--geometry") {
+        Some(index) => match args.get(index + 1) {
+            Some(value) => Geometry::parse(value).unwrap_or_else(|error| panic!("{}", error)),
+            None => panic!("Missing '<row bits>x<collumn bits>' after '--geometry'"),
+        },
+        None => Geometry::new(DEFAULT_ROW_BITS, DEFAULT_COLLUMN_BITS).unwrap(),
+    };
 
-        let row = compute_row(row_code.to_string());
-        let collumn = compute_collumn(collumn_code.to_string());
+    // Encoding mode prints the pass of a given seat instead of reading the input
+    if let Some(index) = args.iter().position(|arg| arg == "--encode") {
+        let seat_code = args.get(index + 1).expect("Missing '<row>,<collumn>' after '--encode'");
+        let (row, collumn) = seat_code.split_once(',')
+            .and_then(|(row, collumn)| Some((row.parse::<u32>().ok()?, collumn.parse::<u32>().ok()?)))
+            .expect("Seat should look like '<row>,<collumn>'");
+
+        match geometry.encode(&build_seat(&geometry, row, collumn)) {
+            Ok(pass) => println!("Boarding pass for row {} collumn {}: {}", row, collumn, pass),
+            Err(error) => println!("Could not encode seat: {}", error),
+        }
+        return;
+    }
+
+    let cabin_rows : Option<(u32, u32)> = args.iter().position(|arg| arg == "--cabin-rows")
+        .map(|index| args.get(index + 1)
+            .and_then(|value| value.split_once('-'))
+            .and_then(|(first, last)| Some((first.parse().ok()?, last.parse().ok()?)))
+            .expect("Cabin rows should look like '<first>-<last>'"));
+    // The cabin is only mapped when a report or the map is asked for
+    let mut occupancy : Option<Occupancy> = if args.iter().any(|arg| arg == "--occupancy" || arg == "--map") {
+        Some(Occupancy::new(&geometry, cabin_rows).unwrap_or_else(|error| panic!("{}", error)))
+    } else {
+        None
+    };
 
-        let new_seat = build_seat(row, collumn);
-        seen_seats.push(new_seat);
+    let mut seen_seats : Vec<Seat> = Vec::new();
+    for (index_line, line) in data.iter().enumerate() {
+        match geometry.decode(line) {
+            Ok(new_seat) => {
+                if let Some(occupancy) = occupancy.as_mut() { occupancy.add_seat(index_line + 1, &new_seat); }
+                seen_seats.push(new_seat);
+            },
+            Err(error) => println!("Ignoring pass '{}' on line {}: {}", line, index_line + 1, error),
+        }
     }
 
     let max_id : i32 = find_biggest_id(&seen_seats);
     println!("Seat with biggest id: {}", max_id);
-    let missing_id : i32 = find_free_space(&seen_seats);
-    println!("Free space on the plane: {}", missing_id);
-}
-
-fn compute_row(value : String) -> u32 {
-    const LOWER_HALF : char = 'F';
-    const UPPER_HALF : char = 'B';
-
-    let mut min : u32 = 0;
-    let mut max : u32 = MAX_ROW;
-
-    for character in value.chars() {
-        let mid_point : f32 = min as f32 + (max as f32 - min as f32) / 2.0;
-        if character == LOWER_HALF { max = mid_point.floor() as u32; }
-        else if character == UPPER_HALF { min = mid_point.ceil() as u32; }
+    let missing_ids : Vec<u32> = match occupancy.as_ref() {
+        Some(occupancy) => occupancy.enclosed_free_seats(),
+        None => find_enclosed_free_ids(&seen_seats),
+    };
+    for missing_id in missing_ids.iter() {
+        println!("Free space on the plane: {}", missing_id);
     }
 
-    assert_eq!(min, max);
-    return min;
+    if let Some(occupancy) = occupancy.as_ref() {
+        if args.iter().any(|arg| arg == "--occupancy") { occupancy.print_report(); }
+        if args.iter().any(|arg| arg == "--map") { print!("{}", occupancy.render()); }
+    }
 }
 
-fn compute_collumn(value : String) -> u32 {
-    const LOWER_HALF : char = 'L';
-    const UPPER_HALF : char = 'R';
-
-    let mut min : u32 = 0;
-    let mut max : u32 = MAX_COLLUMN;
-
-    for character in value.chars() {
-        let mid_point : f32 = min as f32 + (max as f32 - min as f32) / 2.0;
-        if character == LOWER_HALF { max = mid_point.floor() as u32; }
-        else if character == UPPER_HALF { min = mid_point.ceil() as u32; }
-    }
+// Free ids with both neighbouring ids taken, which is where a missing pass should be
+fn find_enclosed_free_ids(seats : &[Seat]) -> Vec<u32> {
+    let taken : HashSet<u32> = seats.iter().map(|seat| seat.id as u32).collect();
 
-    assert_eq!(min, max);
-    return min;
+    let mut free : Vec<u32> = taken.iter()
+        .filter_map(|&id| id.checked_add(1))
+        .filter(|&id| !taken.contains(&id) && id.checked_add(1).is_some_and(|next| taken.contains(&next)))
+        .collect();
+    free.sort_unstable();
+    free
 }
 
-fn find_biggest_id(seats : &Vec<Seat>) -> i32 {
+fn find_biggest_id(seats : &[Seat]) -> i32 {
     let mut max_id : i32 = -1;
 
     for seat in seats.iter() {
         if seat.id > max_id { max_id = seat.id; }
     }
 
-    return max_id;
-}
-
-fn formula_id(row : u32, collumn : u32) -> u32 {
-    return row * 8 + collumn;
-}
-
-fn find_free_space(seats : &Vec<Seat>) -> i32 {
-    let mut min : i32 = -1;
-    let mut max : i32 = -1;
-    let mut sum : i32 = 0;
-
-    for seat in seats.iter() {
-        sum = sum + seat.id;
-        if seat.id == 577 { println!("Found It"); }
-
-        if min == -1 || seat.id < min { min = seat.id; }
-        if max == -1 || seat.id > max { max = seat.id; }
-    }
-
-    let min_sum_total = compute_sum_till_n(min - 1);
-    let max_sum_total = compute_sum_till_n(max);
-    println!("Min Total = {}", min_sum_total);
-    println!("Sum = {}", sum);
-    println!("Sum Total = {}", max_sum_total);
-
-    let missing_value : i32 = max_sum_total - min_sum_total - sum;
-    return missing_value;
+    max_id
 }
-
-fn compute_sum_till_n(n : i32) -> i32 {
-    return n * (n + 1) / 2;
-}
\ No newline at end of file
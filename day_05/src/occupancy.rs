@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use crate::codec::Geometry;
+use crate::Seat;
+
+pub struct Occupancy {
+    rows: u32,
+    collumns: u32,
+    // Rows that physically exist in the cabin, inclusive
+    cabin_rows: (u32, u32),
+    // Input lines of every pass assigned to each taken seat, by seat id
+    passes: HashMap<u32, Vec<usize>>,
+    // Seats outside the cabin rows as (line, row, collumn)
+    out_of_range: Vec<(usize, u32, u32)>,
+}
+
+pub struct FreeBlock {
+    pub row: u32,
+    pub first_collumn: u32,
+    pub last_collumn: u32,
+}
+
+impl Occupancy {
+    pub fn new(geometry : &Geometry, cabin_rows : Option<(u32, u32)>) -> Result<Occupancy, String> {
+        let cabin_rows = cabin_rows.unwrap_or((0, geometry.rows() - 1));
+        if cabin_rows.0 > cabin_rows.1 || cabin_rows.1 >= geometry.rows() {
+            return Err(format!("Cabin rows {}-{} do not fit in {} rows", cabin_rows.0, cabin_rows.1, geometry.rows()));
+        }
+
+        Ok(Occupancy {
+            rows: geometry.rows(),
+            collumns: geometry.collumns(),
+            cabin_rows,
+            passes: HashMap::new(),
+            out_of_range: Vec::new(),
+        })
+    }
+
+    fn in_cabin(&self, row : u32) -> bool {
+        (self.cabin_rows.0..=self.cabin_rows.1).contains(&row)
+    }
+
+    fn index(&self, row : u32, collumn : u32) -> u32 {
+        row * self.collumns + collumn
+    }
+
+    fn is_taken(&self, index : u32) -> bool {
+        self.passes.contains_key(&index)
+    }
+
+    pub fn add_seat(&mut self, line : usize, seat : &Seat) {
+        if !self.in_cabin(seat.row) {
+            self.out_of_range.push((line, seat.row, seat.collumn));
+            return;
+        }
+
+        let index = self.index(seat.row, seat.collumn);
+        self.passes.entry(index).or_default().push(line);
+    }
+
+    pub fn free_seats(&self) -> Vec<(u32, u32)> {
+        (self.cabin_rows.0..=self.cabin_rows.1)
+            .flat_map(|row| (0..self.collumns).map(move |collumn| (row, collumn)))
+            .filter(|&(row, collumn)| !self.is_taken(self.index(row, collumn)))
+            .collect()
+    }
+
+    // Free seats with both neighbouring ids taken, which is where a missing pass should be
+    pub fn enclosed_free_seats(&self) -> Vec<u32> {
+        let mut free : Vec<u32> = self.passes.keys()
+            .filter_map(|&index| index.checked_add(1))
+            .filter(|&index| self.in_cabin(index / self.collumns))
+            .filter(|&index| !self.is_taken(index) && index.checked_add(1).is_some_and(|next| self.is_taken(next)))
+            .collect();
+        free.sort_unstable();
+        free
+    }
+
+    pub fn free_blocks(&self) -> Vec<FreeBlock> {
+        let mut blocks : Vec<FreeBlock> = Vec::new();
+
+        for row in self.cabin_rows.0..=self.cabin_rows.1 {
+            let mut start : Option<u32> = None;
+            for collumn in 0..=self.collumns {
+                let free = collumn < self.collumns && !self.is_taken(self.index(row, collumn));
+                match (free, start) {
+                    (true, None) => start = Some(collumn),
+                    (false, Some(first_collumn)) => {
+                        blocks.push(FreeBlock { row, first_collumn, last_collumn: collumn - 1 });
+                        start = None;
+                    },
+                    _ => (),
+                }
+            }
+        }
+
+        blocks
+    }
+
+    // Seats claimed by more than one pass, with the lines of every such pass
+    pub fn duplicates(&self) -> Vec<(u32, u32, &[usize])> {
+        let mut duplicates : Vec<(u32, u32, &[usize])> = self.passes.iter()
+            .filter(|(_, lines)| lines.len() > 1)
+            .map(|(&index, lines)| (index / self.collumns, index % self.collumns, lines.as_slice()))
+            .collect();
+        duplicates.sort_unstable_by_key(|&(row, collumn, _)| (row, collumn));
+        duplicates
+    }
+
+    pub fn print_report(&self) {
+        let free_seats = self.free_seats();
+        println!("Free seats ({}):", free_seats.len());
+        for (row, collumn) in free_seats.iter() {
+            println!("\tRow {} collumn {}", row, collumn);
+        }
+
+        println!("Contiguous free blocks:");
+        for block in self.free_blocks().iter() {
+            println!("\tRow {}: collumns {}-{} ({} seats)", block.row, block.first_collumn, block.last_collumn,
+                block.last_collumn - block.first_collumn + 1);
+        }
+
+        println!("Duplicated boarding passes:");
+        for (row, collumn, lines) in self.duplicates().iter() {
+            println!("\tRow {} collumn {} claimed on lines {:?}", row, collumn, lines);
+        }
+
+        println!("Seats outside the cabin (rows {}-{}):", self.cabin_rows.0, self.cabin_rows.1);
+        for (line, row, collumn) in self.out_of_range.iter() {
+            println!("\tLine {}: row {} collumn {}", line, row, collumn);
+        }
+    }
+
+    pub fn render(&self) -> String {
+        // Aisle goes down the middle of the cabin
+        let aisle = self.collumns / 2;
+        let row_width = (self.rows - 1).to_string().len();
+
+        let mut output = String::new();
+        for row in 0..self.rows {
+            output.push_str(&format!("{:>width$} ", row, width = row_width));
+            for collumn in 0..self.collumns {
+                if collumn == aisle && self.collumns > 1 { output.push(' '); }
+
+                let symbol = if !self.in_cabin(row) { ' ' } else {
+                    match self.passes.get(&self.index(row, collumn)).map(|lines| lines.len()).unwrap_or(0) {
+                        0 => '.',
+                        1 => '#',
+                        _ => 'D',
+                    }
+                };
+                output.push(symbol);
+            }
+            output.truncate(output.trim_end().len());
+            output.push('\n');
+        }
+
+        output.push_str("'#' taken, '.' free, 'D' duplicated pass\n");
+        output
+    }
+}
@@ -0,0 +1,178 @@
+use std::fmt;
+
+// Answers are kept as a bitmask, so an alphabet can have at most 64 questions
+const MAX_QUESTIONS : usize = 64;
+
+pub struct Alphabet {
+    questions: Vec<char>,
+}
+
+impl Alphabet {
+    pub fn new(questions : &str) -> Result<Alphabet, String> {
+        let mut unique : Vec<char> = Vec::new();
+        for question in questions.chars() {
+            if unique.contains(&question) { return Err(format!("Question '{}' appears twice in the alphabet", question)); }
+            unique.push(question);
+        }
+
+        if unique.is_empty() || unique.len() > MAX_QUESTIONS {
+            return Err(format!("Alphabet should have between 1 and {} questions, found {}", MAX_QUESTIONS, unique.len()));
+        }
+
+        Ok(Alphabet { questions: unique })
+    }
+
+    pub fn default_alphabet() -> Alphabet {
+        Alphabet { questions: ('a'..='z').collect() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.questions.len()
+    }
+
+    pub fn question(&self, index : usize) -> char {
+        self.questions[index]
+    }
+
+    // Answers of a person, along with the characthers that are not questions of the alphabet
+    pub fn parse_person(&self, line : &str) -> (u64, Vec<char>) {
+        let mut answers : u64 = 0;
+        let mut unknown : Vec<char> = Vec::new();
+        for characther in line.chars() {
+            match self.questions.iter().position(|&question| question == characther) {
+                Some(index) => answers |= 1 << index,
+                None => unknown.push(characther),
+            }
+        }
+
+        (answers, unknown)
+    }
+
+    pub fn format(&self, answers : u64) -> String {
+        self.questions.iter().enumerate()
+            .filter(|(index, _)| answers >> index & 1 == 1)
+            .map(|(_, &question)| question)
+            .collect()
+    }
+}
+
+pub struct Group {
+    people: Vec<u64>,
+}
+
+impl Group {
+    pub fn new() -> Group {
+        Group { people: Vec::new() }
+    }
+
+    pub fn add_person(&mut self, answers : u64) {
+        self.people.push(answers);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.people.is_empty()
+    }
+
+    pub fn size(&self) -> usize {
+        self.people.len()
+    }
+
+    pub fn union(&self) -> u64 {
+        self.people.iter().fold(0, |acc, &answers| acc | answers)
+    }
+
+    pub fn intersection(&self) -> u64 {
+        if self.people.is_empty() { return 0; }
+        self.people.iter().fold(u64::MAX, |acc, &answers| acc & answers)
+    }
+
+    // Number of people that answered each question
+    pub fn frequencies(&self, alphabet : &Alphabet) -> Vec<usize> {
+        (0..alphabet.len())
+            .map(|index| self.people.iter().filter(|&&answers| answers >> index & 1 == 1).count())
+            .collect()
+    }
+
+    fn answered_by(&self, alphabet : &Alphabet, accept : impl Fn(usize) -> bool) -> u64 {
+        self.frequencies(alphabet).iter().enumerate()
+            .filter(|(_, &count)| accept(count))
+            .fold(0, |acc, (index, _)| acc | 1 << index)
+    }
+
+    pub fn answered_by_at_least(&self, alphabet : &Alphabet, minimum : usize) -> u64 {
+        self.answered_by(alphabet, |count| count >= minimum && count > 0)
+    }
+
+    pub fn answered_by_exactly(&self, alphabet : &Alphabet, exact : usize) -> u64 {
+        self.answered_by(alphabet, |count| count == exact && count > 0)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum Query {
+    Union,
+    Intersection,
+    AtLeast(usize),
+    Exactly(usize),
+}
+
+impl Query {
+    pub fn parse(value : &str) -> Result<Query, String> {
+        let (name, argument) = match value.split_once(':') {
+            Some((name, argument)) => (name, Some(argument)),
+            None => (value, None),
+        };
+        let count = || argument.and_then(|argument| argument.parse::<usize>().ok())
+            .ok_or(format!("Query '{}' needs a number of people, e.g. '{}:2'", name, name));
+
+        match name {
+            "union" => Ok(Query::Union),
+            "intersection" => Ok(Query::Intersection),
+            "at-least" => Ok(Query::AtLeast(count()?)),
+            "exactly" => Ok(Query::Exactly(count()?)),
+            "exactly-one" => Ok(Query::Exactly(1)),
+            _ => Err(format!("Unknown query '{}' (expected union, intersection, at-least:k, exactly:k or exactly-one)", value)),
+        }
+    }
+
+    pub fn evaluate(&self, alphabet : &Alphabet, group : &Group) -> u64 {
+        match *self {
+            Query::Union => group.union(),
+            Query::Intersection => group.intersection(),
+            Query::AtLeast(minimum) => group.answered_by_at_least(alphabet, minimum),
+            Query::Exactly(exact) => group.answered_by_exactly(alphabet, exact),
+        }
+    }
+
+    // Sum over all groups of the number of questions selected by the query
+    pub fn sum(&self, alphabet : &Alphabet, groups : &[Group]) -> u32 {
+        groups.iter().map(|group| self.evaluate(alphabet, group).count_ones()).sum()
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Query::Union => write!(f, "At least one yes"),
+            Query::Intersection => write!(f, "All yes"),
+            Query::AtLeast(minimum) => write!(f, "Answered by at least {} person(s)", minimum),
+            Query::Exactly(exact) => write!(f, "Answered by exactly {} person(s)", exact),
+        }
+    }
+}
+
+// For every question, number of people and of groups that answered it
+pub fn histogram(alphabet : &Alphabet, groups : &[Group]) -> Vec<(char, usize, usize)> {
+    let mut histogram : Vec<(char, usize, usize)> = (0..alphabet.len())
+        .map(|index| (alphabet.question(index), 0, 0))
+        .collect();
+
+    for group in groups.iter() {
+        for (entry, count) in histogram.iter_mut().zip(group.frequencies(alphabet)) {
+            entry.1 += count;
+            if count > 0 { entry.2 += 1; }
+        }
+    }
+
+    histogram
+}
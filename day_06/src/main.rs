@@ -1,41 +1,13 @@
+use std::env;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::collections::HashSet;
 
-struct Group {
-    at_least_one_yes : HashSet<char>,
-    all_yes : HashSet<char>
-}
+mod answers;
+mod stats;
 
-impl Group {
-    pub fn new() -> Group {
-        Group {
-            at_least_one_yes : HashSet::new(),
-            all_yes : HashSet::new(),
-        }
-    }
-    
-    fn get_all(&self) -> HashSet<char> {
-        return self.all_yes.clone();
-    }
+use answers::{Alphabet, Group, Query};
 
-    fn insert_at_least_one(&mut self, elem : char) {
-        self.at_least_one_yes.insert(elem);
-    }
-    fn insert_all(&mut self, elem : char) {
-        self.all_yes.insert(elem);
-    }
-    fn remove_all(&mut self, elem : char) {
-        self.all_yes.remove(&elem);
-    }
-
-    fn number_questions_at_least_one_yes(&self) -> usize {
-        return self.at_least_one_yes.len();
-    }
-    fn number_questions_all_yes(&self) -> usize {
-        return self.all_yes.len();
-    }
-}
+const DEFAULT_SIMILARITY : f64 = 0.9;
 
 fn main() {
     let filename = "src/input.txt";
@@ -46,49 +18,71 @@ fn main() {
     let data : Vec<String> = reader.lines()
         .collect::<Result<_, _>>().unwrap();
 
+    let args : Vec<String> = env::args().skip(1).collect();
+    let alphabet : Alphabet = match args.iter().position(|arg| arg == "--alphabet") {
+        Some(index) => match args.get(index + 1) {
+            Some(questions) => Alphabet::new(questions).unwrap_or_else(|error| panic!("{}", error)),
+            None => panic!("Missing questions after '--alphabet'"),
+        },
+        None => Alphabet::default_alphabet(),
+    };
+
     let mut groups : Vec<Group> = Vec::new();
     let mut new_group : Group = Group::new();
-    let mut first_person_in_group : bool = true;
-    for line in data.iter() {
+    for (index_line, line) in data.iter().enumerate() {
         let char_count = line.chars().count();
         if char_count == 0 {
-            groups.push(new_group);
+            if !new_group.is_empty() { groups.push(new_group); }
             new_group = Group::new();
-            first_person_in_group = true;
             continue;
         }
 
-        let mut to_keep : HashSet<char> = HashSet::new();
-        for characther in line.chars() {
-            to_keep.insert(characther);
-            new_group.insert_at_least_one(characther);
+        // Only the unknown characthers are left out, the person still answered the rest
+        let (answers, unknown) = alphabet.parse_person(line);
+        if !unknown.is_empty() {
+            println!("Line {}: ignoring {:?}, not questions of the alphabet", index_line + 1, unknown);
         }
+        new_group.add_person(answers);
+    }
+    if !new_group.is_empty() { groups.push(new_group); }
+
+    // The two original sums, followed by any extra query asked for
+    let mut queries : Vec<Query> = vec![Query::Union, Query::Intersection];
+    for (index, arg) in args.iter().enumerate() {
+        if arg != "--query" { continue; }
 
-        // If first person in group add all
-        if first_person_in_group {
-            for characther in to_keep {
-                new_group.insert_all(characther);
-            }
-        // Else remove elements present not in new persons list
-        } else {
-            for characther in new_group.get_all() {
-                if ! to_keep.contains(&characther) { new_group.remove_all(characther); }
-            }
+        let value = args.get(index + 1).expect("Missing query after '--query'");
+        queries.push(Query::parse(value).unwrap_or_else(|error| panic!("{}", error)));
+    }
+
+    for query in queries.iter() {
+        println!("{}: {}", query, query.sum(&alphabet, &groups));
+    }
+
+    if args.iter().any(|arg| arg == "--histogram") {
+        println!("Question\tPeople\tGroups");
+        for (question, people, answered_groups) in answers::histogram(&alphabet, &groups).iter() {
+            println!("{}\t\t{}\t{}", question, people, answered_groups);
         }
-        
-        // Update not first person in group anymore
-        first_person_in_group = false;
     }
-    groups.push(new_group);
 
-    let mut sum_at_least_one : u32 = 0;
-    let mut sum_all : u32 = 0;
-    for group in groups.iter() {
-        sum_at_least_one = sum_at_least_one + group.number_questions_at_least_one_yes() as u32;
-        sum_all = sum_all + group.number_questions_all_yes() as u32;
+    // Statistics export, as '<prefix>_groups.csv' and '<prefix>_questions.csv' or a single JSON file
+    if let Some(index) = args.iter().position(|arg| arg == "--stats") {
+        let format = args.get(index + 1).expect("Missing 'csv' or 'json' after '--stats'");
+        let output = args.get(index + 2).expect("Missing output after the statistics format");
+
+        match stats::build_stats(&alphabet, &groups).export(format, output) {
+            Ok(files) => println!("Statistics written to {}", files.join(", ")),
+            Err(error) => println!("{}", error),
+        }
     }
 
-    println!("At least one yes: {}", sum_at_least_one);
-    println!("All yes: {}", sum_all);
+    if let Some(index) = args.iter().position(|arg| arg == "--similar") {
+        let threshold : f64 = args.get(index + 1).and_then(|value| value.parse().ok()).unwrap_or(DEFAULT_SIMILARITY);
 
+        println!("Groups with Jaccard similarity of at least {}:", threshold);
+        for similarity in stats::similar_groups(&groups, threshold).iter() {
+            println!("\tGroups {} and {}: {:.3}", similarity.first_group, similarity.second_group, similarity.jaccard);
+        }
+    }
 }
@@ -0,0 +1,161 @@
+use std::fs;
+
+use serde::Serialize;
+
+use crate::answers::{Alphabet, Group};
+
+#[derive(Serialize)]
+pub struct GroupStats {
+    group: usize,
+    size: usize,
+    distinct: u32,
+    unanimous: u32,
+    // Questions answered by the most (least) people in the group, ties included
+    most_common: String,
+    least_common: String,
+}
+
+#[derive(Serialize)]
+pub struct QuestionStats {
+    question: char,
+    people: usize,
+    groups: usize,
+    unanimous_groups: usize,
+}
+
+pub struct Similarity {
+    pub first_group: usize,
+    pub second_group: usize,
+    pub jaccard: f64,
+}
+
+#[derive(Serialize)]
+pub struct SurveyStats {
+    groups: Vec<GroupStats>,
+    questions: Vec<QuestionStats>,
+    // Questions answered by the most (least) people across all groups
+    most_common: String,
+    least_common: String,
+}
+
+// Questions whose count is the maximum (or minimum) among those answered at least once
+fn extreme_questions(alphabet : &Alphabet, counts : &[usize], most : bool) -> String {
+    let answered = counts.iter().filter(|&&count| count > 0);
+    let target = if most { answered.max() } else { answered.min() };
+
+    match target {
+        Some(&target) => {
+            let mask = counts.iter().enumerate()
+                .filter(|(_, &count)| count == target)
+                .fold(0, |acc, (index, _)| acc | 1 << index);
+            alphabet.format(mask)
+        },
+        None => String::new(),
+    }
+}
+
+pub fn build_stats(alphabet : &Alphabet, groups : &[Group]) -> SurveyStats {
+    let mut questions : Vec<QuestionStats> = (0..alphabet.len())
+        .map(|index| QuestionStats { question: alphabet.question(index), people: 0, groups: 0, unanimous_groups: 0 })
+        .collect();
+
+    let mut group_stats : Vec<GroupStats> = Vec::new();
+    for (index, group) in groups.iter().enumerate() {
+        let frequencies = group.frequencies(alphabet);
+        for (question, &count) in questions.iter_mut().zip(frequencies.iter()) {
+            question.people += count;
+            if count > 0 { question.groups += 1; }
+            if count == group.size() { question.unanimous_groups += 1; }
+        }
+
+        group_stats.push(GroupStats {
+            group: index + 1,
+            size: group.size(),
+            distinct: group.union().count_ones(),
+            unanimous: group.intersection().count_ones(),
+            most_common: extreme_questions(alphabet, &frequencies, true),
+            least_common: extreme_questions(alphabet, &frequencies, false),
+        });
+    }
+
+    let totals : Vec<usize> = questions.iter().map(|question| question.people).collect();
+    SurveyStats {
+        groups: group_stats,
+        questions,
+        most_common: extreme_questions(alphabet, &totals, true),
+        least_common: extreme_questions(alphabet, &totals, false),
+    }
+}
+
+fn jaccard(first : u64, second : u64) -> f64 {
+    let union = (first | second).count_ones();
+    if union == 0 { return 1.0; }
+    (first & second).count_ones() as f64 / union as f64
+}
+
+// Pairs of groups whose distinct answers are at least `threshold` similar, most similar first
+pub fn similar_groups(groups : &[Group], threshold : f64) -> Vec<Similarity> {
+    let unions : Vec<u64> = groups.iter().map(|group| group.union()).collect();
+
+    let mut similar : Vec<Similarity> = Vec::new();
+    for first in 0..unions.len() {
+        for second in first + 1..unions.len() {
+            let value = jaccard(unions[first], unions[second]);
+            if value >= threshold {
+                similar.push(Similarity { first_group: first + 1, second_group: second + 1, jaccard: value });
+            }
+        }
+    }
+
+    similar.sort_by(|a, b| b.jaccard.total_cmp(&a.jaccard));
+    similar
+}
+
+// Questions come from the alphabet, which may hold ',' or '"'
+fn escape_csv(cell : &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+impl SurveyStats {
+    pub fn groups_csv(&self) -> String {
+        let mut output = "group,size,distinct,unanimous,most_common,least_common\n".to_string();
+        for group in self.groups.iter() {
+            output.push_str(&format!("{},{},{},{},{},{}\n", group.group, group.size, group.distinct,
+                group.unanimous, escape_csv(&group.most_common), escape_csv(&group.least_common)));
+        }
+
+        output
+    }
+
+    pub fn questions_csv(&self) -> String {
+        let mut output = "question,people,groups,unanimous_groups\n".to_string();
+        for question in self.questions.iter() {
+            output.push_str(&format!("{},{},{},{}\n", escape_csv(&question.question.to_string()), question.people, question.groups, question.unanimous_groups));
+        }
+
+        output
+    }
+
+    pub fn export(&self, format : &str, output : &str) -> Result<Vec<String>, String> {
+        let files : Vec<(String, String)> = match format {
+            "csv" => vec![
+                (format!("{}_groups.csv", output), self.groups_csv()),
+                (format!("{}_questions.csv", output), self.questions_csv()),
+            ],
+            "json" => vec![
+                (output.to_string(), serde_json::to_string_pretty(self).map_err(|error| error.to_string())?),
+            ],
+            _ => return Err(format!("Unknown statistics format '{}' (expected 'csv' or 'json')", format)),
+        };
+
+        for (filename, content) in files.iter() {
+            fs::write(filename, content).map_err(|error| format!("Could not write '{}': {}", filename, error))?;
+        }
+
+        Ok(files.into_iter().map(|(filename, _)| filename).collect())
+    }
+}
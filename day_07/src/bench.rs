@@ -0,0 +1,103 @@
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+
+use crate::parse_rules;
+use crate::rules::Rules;
+
+const ADJECTIVES : [&str; 12] = ["light", "dark", "bright", "muted", "shiny", "faded", "dotted", "pale", "posh", "vibrant", "wavy", "striped"];
+const COLOURS : [&str; 12] = ["red", "orange", "white", "yellow", "gold", "olive", "plum", "blue", "black", "teal", "tan", "coral"];
+const MAX_CONTENTS : u64 = 4;
+
+// Small xorshift generator, so generated rule sets are reproducible
+struct Generator {
+    state : u64,
+}
+
+impl Generator {
+    fn below(&mut self, upper : u64) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state % upper
+    }
+}
+
+// Names only use letters, the index is written in base 26 after an "x" so it never reads as "bag"
+fn bag_name(index : usize) -> String {
+    let adjective = ADJECTIVES[index % ADJECTIVES.len()];
+    let colour = COLOURS[(index / ADJECTIVES.len()) % COLOURS.len()];
+
+    let mut suffix : Vec<char> = Vec::new();
+    let mut remaining = index;
+    loop {
+        suffix.push((b'a' + (remaining % 26) as u8) as char);
+        remaining /= 26;
+        if remaining == 0 { break; }
+    }
+
+    format!("{} {} x{}", adjective, colour, suffix.iter().rev().collect::<String>())
+}
+
+// Acyclic rule set where every bag only contains bags defined after it
+pub fn generate_rules(number_rules : usize) -> Vec<String> {
+    let mut generator = Generator { state : 0x2020_0007 };
+
+    (0..number_rules).map(|index| {
+        let remaining = (number_rules - index - 1) as u64;
+        let number_contents = if remaining == 0 { 0 } else { generator.below(MAX_CONTENTS + 1) };
+        if number_contents == 0 {
+            return format!("{} bags contain no other bags.", bag_name(index));
+        }
+
+        let contents : Vec<String> = (0..number_contents).map(|_| {
+            let quantity = 1 + generator.below(5);
+            let sub_bag = index + 1 + generator.below(remaining) as usize;
+            format!("{} {} {}", quantity, bag_name(sub_bag), if quantity == 1 { "bag" } else { "bags" })
+        }).collect();
+        format!("{} bags contain {}.", bag_name(index), contents.join(", "))
+    }).collect()
+}
+
+// The original regular expression pipeline, kept as a reference point
+pub fn parse_rules_regex(data : &[String]) -> Rules {
+    let regex_doesnt_contain = Regex::new(r"^(.+) bags contain no other bags.$").unwrap();
+    let regex_contain_sep = Regex::new(r"^(.+) bags contain( (?:\d+) (?:.+) (?:bag|bags)(?:,|\.))+$").unwrap();
+    let regex_contain_each = Regex::new(r"^(\d+) (.+) (?:bag|bags)+$").unwrap();
+
+    let mut rules : Rules = Rules::new();
+
+    for (index_line, line) in data.iter().enumerate() {
+        if let Some(cap) = regex_contain_sep.captures(line) {
+            let contains : String = cap[2].trim().replace(".", "");
+            let bag = rules.add_bag(&cap[1], index_line + 1);
+
+            for contain in contains.split(", ") {
+                let sub_cap : regex::Captures = regex_contain_each.captures(contain).unwrap();
+                rules.add_contain_to_bag(bag, sub_cap[1].parse().unwrap(), &sub_cap[2]);
+            }
+        } else if let Some(cap) = regex_doesnt_contain.captures(line) {
+            rules.add_bag(&cap[1], index_line + 1);
+        }
+    }
+
+    rules
+}
+
+fn time<T>(action : impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = action();
+    (result, start.elapsed())
+}
+
+pub fn run(number_rules : usize) {
+    let data = generate_rules(number_rules);
+
+    let (regex_rules, regex_time) = time(|| parse_rules_regex(&data));
+    let ((parsed_rules, errors), parser_time) = time(|| parse_rules(&data));
+
+    println!("Generated {} rules", data.len());
+    println!("Regex pipeline: {} bags in {:?}", regex_rules.bags().len(), regex_time);
+    println!("Parser: {} bags in {:?} ({} errors)", parsed_rules.bags().len(), parser_time, errors.len());
+    println!("Speedup: {:.2}x", regex_time.as_secs_f64() / parser_time.as_secs_f64());
+}
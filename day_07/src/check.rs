@@ -0,0 +1,139 @@
+use std::collections::BTreeSet;
+
+use crate::rules::{BagError, BagId, Rules};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    New,
+    InPath,
+    Done,
+}
+
+pub struct Problems {
+    // Each cycle as the path of bag names, starting and ending on the same bag
+    pub cycles: Vec<Vec<String>>,
+    // Bags referenced inside a rule without a rule of their own, with the bags referencing them
+    pub dangling: Vec<(String, Vec<String>)>,
+    // Bags defined by more than one rule, with the lines of those rules
+    pub duplicates: Vec<(String, Vec<usize>)>,
+    // Bags that can neither contain nor be contained by the target bag
+    pub unreachable: Vec<String>,
+}
+
+impl Problems {
+    pub fn is_empty(&self) -> bool {
+        self.cycles.is_empty() && self.dangling.is_empty() && self.duplicates.is_empty()
+    }
+}
+
+fn find_cycles(rules : &Rules) -> Vec<Vec<String>> {
+    let bags = rules.bags();
+    let mut visits : Vec<Visit> = vec![Visit::New; bags.len()];
+    let mut cycles : Vec<Vec<String>> = Vec::new();
+
+    for root in 0..bags.len() {
+        if visits[root] != Visit::New { continue; }
+
+        // Iterative depth first search, the stack holds the current path and the next edge to follow
+        let mut stack : Vec<(BagId, usize)> = vec![(root, 0)];
+        visits[root] = Visit::InPath;
+
+        while let Some(&(current, next_edge)) = stack.last() {
+            let sub_bags = bags[current].get_sub_bags();
+            if next_edge == sub_bags.len() {
+                visits[current] = Visit::Done;
+                stack.pop();
+                continue;
+            }
+
+            let sub_bag = sub_bags[next_edge].get_sub_bag();
+            if let Some(last) = stack.last_mut() { last.1 += 1; }
+
+            match visits[sub_bag] {
+                Visit::New => {
+                    visits[sub_bag] = Visit::InPath;
+                    stack.push((sub_bag, 0));
+                },
+                Visit::InPath => {
+                    let start = stack.iter().position(|&(id, _)| id == sub_bag).unwrap();
+                    let mut cycle : Vec<String> = stack[start..].iter()
+                        .map(|&(id, _)| bags[id].get_bag_name().to_string())
+                        .collect();
+                    cycle.push(bags[sub_bag].get_bag_name().to_string());
+                    cycles.push(cycle);
+                },
+                Visit::Done => (),
+            }
+        }
+    }
+
+    cycles
+}
+
+fn connected_to(rules : &Rules, target : &str) -> Result<BTreeSet<BagId>, BagError> {
+    let mut connected : BTreeSet<BagId> = rules.contents_of(target)?;
+    connected.extend(rules.containers_of(target)?.iter());
+    connected.insert(rules.find_bag(target)?);
+
+    Ok(connected)
+}
+
+pub fn check_rules(rules : &Rules, target : Option<&str>) -> Problems {
+    let bags = rules.bags();
+
+    let dangling = bags.iter().enumerate()
+        .filter(|(_, bag)| !bag.is_declared())
+        .map(|(id, bag)| {
+            let referenced_by : Vec<String> = rules.direct_containers(id).iter()
+                .map(|&container| bags[container].get_bag_name().to_string())
+                .collect();
+            (bag.get_bag_name().to_string(), referenced_by)
+        })
+        .collect();
+
+    let duplicates = bags.iter()
+        .filter(|bag| bag.get_definitions().len() > 1)
+        .map(|bag| (bag.get_bag_name().to_string(), bag.get_definitions().to_vec()))
+        .collect();
+
+    let unreachable = match target.map(|target| connected_to(rules, target)) {
+        Some(Ok(connected)) => {
+            bags.iter().enumerate()
+                .filter(|(id, _)| !connected.contains(id))
+                .map(|(_, bag)| bag.get_bag_name().to_string())
+                .collect()
+        },
+        _ => Vec::new(),
+    };
+
+    Problems {
+        cycles: find_cycles(rules),
+        dangling,
+        duplicates,
+        unreachable,
+    }
+}
+
+pub fn print_problems(problems : &Problems, target : Option<&str>) {
+    println!("Cycles: {}", problems.cycles.len());
+    for cycle in problems.cycles.iter() {
+        println!("\t{}", cycle.join(" -> "));
+    }
+
+    println!("Dangling references: {}", problems.dangling.len());
+    for (bag_name, referenced_by) in problems.dangling.iter() {
+        println!("\t'{}' has no rule, referenced by: {}", bag_name, referenced_by.join(", "));
+    }
+
+    println!("Duplicate definitions: {}", problems.duplicates.len());
+    for (bag_name, lines) in problems.duplicates.iter() {
+        println!("\t'{}' defined on lines {:?}", bag_name, lines);
+    }
+
+    if let Some(target) = target {
+        println!("Bags unrelated to '{}': {}", target, problems.unreachable.len());
+        for bag_name in problems.unreachable.iter() {
+            println!("\t{}", bag_name);
+        }
+    }
+}
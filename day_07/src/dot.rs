@@ -0,0 +1,79 @@
+use std::collections::BTreeSet;
+use std::fs;
+
+use crate::rules::{BagError, BagId, Rules};
+
+const TARGET_COLOUR : &str = "gold";
+const ANCESTOR_COLOUR : &str = "lightblue";
+const DESCENDANT_COLOUR : &str = "palegreen";
+
+#[derive(Clone, Copy)]
+pub enum Scope {
+    // Every bag in the rules
+    All,
+    // Bags containing the target or inside it
+    Around,
+    // Only bags containing the target
+    Containers,
+    // Only bags inside the target
+    Contents,
+}
+
+impl Scope {
+    pub fn parse(value : &str) -> Result<Scope, String> {
+        match value {
+            "all" => Ok(Scope::All),
+            "around" => Ok(Scope::Around),
+            "containers" => Ok(Scope::Containers),
+            "contents" => Ok(Scope::Contents),
+            _ => Err(format!("Unknown scope '{}' (expected all, around, containers or contents)", value)),
+        }
+    }
+}
+
+fn escape(bag_name : &str) -> String {
+    bag_name.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+pub fn to_dot(rules : &Rules, target : &str, scope : Scope) -> Result<String, BagError> {
+    let target_id : BagId = rules.find_bag(target)?;
+    let ancestors = rules.containers_of(target)?;
+    let descendants = rules.contents_of(target)?;
+
+    let included : BTreeSet<BagId> = match scope {
+        Scope::All => (0..rules.bags().len()).collect(),
+        Scope::Around => ancestors.union(&descendants).copied().chain(Some(target_id)).collect(),
+        Scope::Containers => ancestors.iter().copied().chain(Some(target_id)).collect(),
+        Scope::Contents => descendants.iter().copied().chain(Some(target_id)).collect(),
+    };
+
+    let mut output = String::from("digraph rules {\n    node [shape=box, style=filled, fillcolor=white];\n");
+
+    for &id in included.iter() {
+        // A bag inside a cycle is both an ancestor and a descendant, the target colour wins first
+        let colour = if id == target_id { TARGET_COLOUR }
+            else if ancestors.contains(&id) { ANCESTOR_COLOUR }
+            else if descendants.contains(&id) { DESCENDANT_COLOUR }
+            else { "white" };
+        output.push_str(&format!("    \"{}\" [fillcolor={}];\n", escape(rules.bags()[id].get_bag_name()), colour));
+    }
+
+    for &id in included.iter() {
+        for contain in rules.bags()[id].get_sub_bags().iter() {
+            if !included.contains(&contain.get_sub_bag()) { continue; }
+
+            output.push_str(&format!("    \"{}\" -> \"{}\" [label=\"{}\"];\n",
+                escape(rules.bags()[id].get_bag_name()),
+                escape(rules.bags()[contain.get_sub_bag()].get_bag_name()),
+                contain.get_sub_bag_quantity()));
+        }
+    }
+
+    output.push_str("}\n");
+    Ok(output)
+}
+
+pub fn write_dot(filename : &str, rules : &Rules, target : &str, scope : Scope) -> Result<(), String> {
+    let content = to_dot(rules, target, scope).map_err(|error| error.to_string())?;
+    fs::write(filename, content).map_err(|error| format!("Could not write '{}': {}", filename, error))
+}
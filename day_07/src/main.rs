@@ -1,229 +1,172 @@
+use std::env;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use regex::Regex;
+use std::process;
 
-// TODO:
-// Reference to another bag in ContainedBag should be a pointer to Bag instead of its name
-// Errors should not be a simple &str
+mod bench;
+mod check;
+mod dot;
+mod parser;
+mod paths;
+mod rules;
 
-const BAG_NAME : &str = "shiny gold";
+use parser::ParseError;
+use rules::{BagError, Rules};
 
-struct Bag {
-    bag_name : String,
-    sub_bags : Vec<ContainedBag>,
-    visited : bool
-}
+const BAG_NAME : &str = "shiny gold";
+const DEFAULT_BENCH_RULES : usize = 100_000;
 
-impl Bag {
-    pub fn new(bag_name : String) -> Bag {
-        Bag {
-            bag_name : bag_name,
-            sub_bags : Vec::new(),
-            visited : false,
+pub fn parse_rules(data : &[String]) -> (Rules, Vec<ParseError>) {
+    let mut rules : Rules = Rules::new();
+    let mut errors : Vec<ParseError> = Vec::new();
+
+    for (index_line, line) in data.iter().enumerate() {
+        if line.trim().is_empty() { continue; }
+
+        match parser::parse_rule(line, index_line + 1) {
+            Ok(rule) => {
+                let bag = rules.add_bag(&rule.bag_name, index_line + 1);
+                for (quantity, sub_bag_name) in rule.contents.iter() {
+                    rules.add_contain_to_bag(bag, *quantity, sub_bag_name);
+                }
+            },
+            Err(error) => errors.push(error),
         }
     }
 
-    fn get_bag_name(&self) -> String {
-        return self.bag_name.clone();
-    }
-
-    fn get_sub_bags(&self) -> Vec<ContainedBag> {
-        return (&self).sub_bags.clone();
-    }
-
-    fn add_contain_bag(&mut self, quantity : u32, sub_bag_name : String) {
-        let new_sub_bag = ContainedBag::new(quantity, sub_bag_name);
-        self.sub_bags.push(new_sub_bag);
-    }
-
-    fn bag_visited(&self) -> bool {
-        return self.visited;
-    }
-
-    fn set_visited(&mut self, value : bool) {
-        self.visited = value;
-    }
-
-    fn sub_bags_contain(&self, sub_bag_name : String) -> bool {
-        return self.sub_bags.iter()
-            .any(|sub_bag| sub_bag.get_sub_bag_name() == sub_bag_name);
-    }
+    (rules, errors)
 }
 
-#[derive(Clone, Debug)]
-struct ContainedBag {
-    quantity : u32,
-    bag_ref : String
-}
-
-impl ContainedBag {
-    pub fn new(quantity : u32, bag_ref : String) -> ContainedBag {
-        ContainedBag {
-            quantity : quantity,
-            bag_ref : bag_ref
-        }
-    }
-
-    fn get_sub_bag_quantity(&self) -> u32 {
-        return self.quantity;
-    }
+fn main() {
+    let args : Vec<String> = env::args().skip(1).collect();
+
+    // Commands: 'check [file]' validates a rule file, 'dot <output>' exports the rules as a graph,
+    // 'fmt' prints the rules in canonical form and 'bench [rules]' compares parsing speeds,
+    // 'paths <outer>', 'contents' and 'deepest' query how bags are nested
+    let command : Option<&str> = args.first().map(|arg| arg.as_str()).filter(|arg| !arg.starts_with("--"));
+    // The benchmark generates its own rules, no input is needed
+    if command == Some("bench") {
+        let number_rules : usize = args.get(1).and_then(|arg| arg.parse().ok()).unwrap_or(DEFAULT_BENCH_RULES);
+        bench::run(number_rules);
+        return;
+    }
+
+    let filename : &str = match (command, args.get(1), args.iter().position(|arg| arg == "--input")) {
+        (_, _, Some(index)) => args.get(index + 1).expect("Missing file after '--input'"),
+        (Some("check"), Some(file), None) if !file.starts_with("--") => file,
+        _ => "src/input.txt",
+    };
+    let target : &str = match args.iter().position(|arg| arg == "--bag") {
+        Some(index) => args.get(index + 1).expect("Missing bag name after '--bag'"),
+        None => BAG_NAME,
+    };
 
-    fn get_sub_bag_name(&self) -> String {
-        return self.bag_ref.clone();
-    }
-}
+    let file = File::open(filename).unwrap_or_else(|error| panic!("Could not open '{}': {}", filename, error));
+    let reader = BufReader::new(file);
 
-struct Rules {
-    bags : Vec<Bag>
-}
+    let data : Vec<String> = reader.lines()
+        .collect::<Result<_, _>>().unwrap();
 
-impl Rules {
-    pub fn new() -> Rules {
-        Rules {
-            bags : Vec::new()
+    if command == Some("fmt") {
+        for (index_line, line) in data.iter().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            match parser::parse_rule(line, index_line + 1) {
+                Ok(rule) => println!("{}", parser::format_rule(&rule)),
+                Err(error) => println!("{}", error),
+            }
         }
+        return;
     }
 
-    fn add_bag(&mut self, bag_name : String) {
-        if !self.bag_exists(bag_name.clone()) {
-            let new_bag = Bag::new(bag_name);
-            self.bags.push(new_bag);
-        }
+    let (rules, errors) : (Rules, Vec<ParseError>) = parse_rules(&data);
+    for error in errors.iter() {
+        println!("{}", error);
     }
 
-    fn bag_exists(&self, bag_name : String) -> bool {
-        let exists : bool = self.bags.iter()
-            .any(|bag| bag.get_bag_name() == bag_name);
+    match command {
+        Some("check") => {
+            if rules.find_bag(target).is_err() { println!("Bag '{}' does not appear in any rule", target); }
 
-        return exists;
-    }
+            let problems = check::check_rules(&rules, Some(target));
+            check::print_problems(&problems, Some(target));
+            if !problems.is_empty() || !errors.is_empty() { process::exit(1); }
+            return;
+        },
+        Some("dot") => {
+            let output = args.get(1).filter(|arg| !arg.starts_with("--")).expect("Missing output file after 'dot'");
+            let scope = match args.iter().position(|arg| arg == "--scope") {
+                Some(index) => dot::Scope::parse(args.get(index + 1).map(|arg| arg.as_str()).unwrap_or(""))
+                    .unwrap_or_else(|error| panic!("{}", error)),
+                None => dot::Scope::All,
+            };
 
-    fn add_contain_to_bag(&mut self, bag_name : String, quantity : u32, sub_bag_name : String) -> Result<&'static str, &'static str> {
-        for bag in self.bags.iter_mut() {
-            if bag.get_bag_name() == bag_name {
-                bag.add_contain_bag(quantity, sub_bag_name);
-                return Ok("Contained added sucessfully");
+            match dot::write_dot(output, &rules, target, scope) {
+                Ok(()) => println!("Rules graph written to '{}'", output),
+                Err(error) => println!("{}", error),
             }
-        }
-
-        return Err("No bag with such name found!");
-    }
-
-    fn number_bags_can_contain(&mut self, bag_name : String) -> u32 {
-        let mut number_bags : u32 = 0;
-        let mut bags_to_explore : Vec<String> = Vec::new();
-
-        for bag in self.bags.iter_mut() {
-            if bag.bag_visited() { continue; }
-            if bag.sub_bags_contain(bag_name.clone()) {
-                bag.set_visited(true);
-                number_bags = number_bags + 1;
-                bags_to_explore.push(bag.get_bag_name());
+            return;
+        },
+        Some("paths") => {
+            let outer = args.get(1).filter(|arg| !arg.starts_with("--")).expect("Missing outer bag after 'paths'");
+            let limit : Option<usize> = args.iter().position(|arg| arg == "--limit")
+                .and_then(|index| args.get(index + 1))
+                .and_then(|arg| arg.parse().ok());
+
+            match paths::all_paths(&rules, outer, target, limit) {
+                Ok(found) => {
+                    println!("Paths from '{}' to '{}': {}", outer, target, found.len());
+                    for path in found.iter() {
+                        let names : Vec<&str> = path.bags.iter().map(|&id| rules.bags()[id].get_bag_name()).collect();
+                        println!("\t{} ({} bags)", names.join(" -> "), path.quantity);
+                    }
+                    match found.iter().try_fold(0u64, |total, path| total.checked_add(path.quantity)) {
+                        Some(total) => println!("Total '{}' bags inside '{}': {}", target, outer, total),
+                        None => println!("{}", BagError::Overflow(outer.to_string())),
+                    }
+                },
+                Err(error) => println!("{}", error),
             }
-        }
-
-        for bag_name in bags_to_explore {
-            number_bags = number_bags + self.number_bags_can_contain(bag_name);
-        }
-
-        return number_bags;
-    }
-
-    fn number_bags_inside(&mut self, bag_name : String) -> Result<u32, &'static str> {
-        let mut number_bags : u32 = 0;
-        let mut contains : Option<Vec<ContainedBag>> = None;
-
-        for bag in self.bags.iter_mut() {
-            if bag.get_bag_name() == bag_name {
-                contains = Some(bag.get_sub_bags());
-                break;
+            return;
+        },
+        Some("contents") => {
+            match paths::contents_multiset(&rules, target) {
+                Ok(counts) => {
+                    println!("Contents of '{}':", target);
+                    for (&id, count) in counts.iter() {
+                        println!("\t{} x {}", count, rules.bags()[id].get_bag_name());
+                    }
+                    match counts.values().try_fold(0u64, |total, &count| total.checked_add(count)) {
+                        Some(total) => println!("Total: {}", total),
+                        None => println!("{}", BagError::Overflow(target.to_string())),
+                    }
+                },
+                Err(error) => println!("{}", error),
             }
-        }
-
-        let contains = match contains {
-            Some(value) => value,
-            None => {
-                println!("Bag that originated error: {}", bag_name);
-                return Err("Didn't find rule for a bag");
+            return;
+        },
+        Some("deepest") => {
+            match paths::deepest_chain(&rules) {
+                Ok(chain) => {
+                    let names : Vec<&str> = chain.iter().map(|&id| rules.bags()[id].get_bag_name()).collect();
+                    println!("Deepest nesting ({} bags): {}", chain.len(), names.join(" -> "));
+                },
+                Err(error) => println!("{}", error),
             }
-        };
-
-        for contain in contains.iter() {
-            let sub_bag_name : String = contain.get_sub_bag_name();
-            let number_bags_inside_sub : u32 = match self.number_bags_inside(sub_bag_name) {
-                Ok(number) => number,
-                Err(e) => return Err(e),
-            };
-
-            number_bags = number_bags + contain.get_sub_bag_quantity() * (1 + number_bags_inside_sub);
-        }
-
-        return Ok(number_bags);
-    }
-
-    fn clean_bags_visited(&mut self) {
-        for bag in self.bags.iter_mut() {
-            bag.set_visited(false);
-        }
+            return;
+        },
+        Some(other) => panic!("Unknown command '{}' (expected 'check', 'dot', 'fmt', 'bench', 'paths', 'contents' or 'deepest')", other),
+        None => (),
     }
-}
 
-fn main() {
-    let filename = "src/input.txt";
-
-    let file = File::open(filename).unwrap();
-    let reader = BufReader::new(file);
-
-    let data : Vec<String> = reader.lines()
-        .collect::<Result<_, _>>().unwrap();
-
-    let regex_doesnt_contain = Regex::new(r"^(.+) bags contain no other bags.$").unwrap();
-    let regex_contain_sep = Regex::new(r"^(.+) bags contain( (?:\d+) (?:.+) (?:bag|bags)(?:,|\.))+$").unwrap(); 
-    let regex_contain_each = Regex::new(r"^(\d+) (.+) (?:bag|bags)+$").unwrap();
-
-    let mut rules : Rules = Rules::new();
-
-    for line in data.iter() {
-        if regex_contain_sep.is_match(line) {
-            let cap : regex::Captures = regex_contain_sep.captures(line).unwrap();
-
-            let bag_name : String = (&cap[1]).to_string();
-            let contains : String = (&cap[2]).trim().replace(".", "").to_string();
-            
-            rules.add_bag(bag_name.clone());
-
-            let contains_split = contains.split(", ");
-            for contain in contains_split {
-                let sub_cap : regex::Captures = regex_contain_each.captures(contain).unwrap();
-
-                let quantity : u32 = (&sub_cap[1]).to_string().parse().unwrap();
-                let sub_bag_name : String = (&sub_cap[2]).to_string();
-
-                match rules.add_contain_to_bag(bag_name.clone(), quantity, sub_bag_name) {
-                    Ok(_) => (),
-                    Err(e) => println!("{}", e),
-                };
-            }
-
-        } else if regex_doesnt_contain.is_match(line) {
-            let cap : regex::Captures = regex_doesnt_contain.captures(line).unwrap();
-
-            let bag_name : String = (&cap[1]).to_string();
-            rules.add_bag(bag_name);
-
-        } else {
-            println!("Line wasn't parsed: {}", line);
-        }
-    }
+    let number_bags_can_contain : usize = match rules.containers_of(target) {
+        Ok(containers) => containers.len(),
+        Err(e) => panic!("{}", e),
+    };
+    println!("Number of bags that contain '{}': {}", target, number_bags_can_contain);
 
-    let number_bags_can_contain : u32 = rules.number_bags_can_contain(BAG_NAME.to_string());
-    println!("Number of bags that contain '{}': {}", BAG_NAME.to_string(), number_bags_can_contain);
-    rules.clean_bags_visited();
-    
-    let number_bags_inside : u32 = match rules.number_bags_inside(BAG_NAME.to_string()) {
+    let number_bags_inside : u64 = match rules.total_contents(target) {
         Ok(value) => value,
-        Err(e) => panic!(e)
+        Err(e) => panic!("{}", e),
     };
 
-    println!("Number of bags inside '{}': {}", BAG_NAME.to_string(), number_bags_inside);
-}
\ No newline at end of file
+    println!("Number of bags inside '{}': {}", target, number_bags_inside);
+}
@@ -0,0 +1,195 @@
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TokenKind<'a> {
+    Word(&'a str),
+    Number(u32),
+    Comma,
+    Period,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Token<'a> {
+    kind : TokenKind<'a>,
+    // 1-based column of the first character
+    column : usize,
+}
+
+impl fmt::Display for TokenKind<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenKind::Word(word) => write!(f, "'{}'", word),
+            TokenKind::Number(number) => write!(f, "'{}'", number),
+            TokenKind::Comma => write!(f, "','"),
+            TokenKind::Period => write!(f, "'.'"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub line : usize,
+    pub column : usize,
+    pub message : String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RuleLine {
+    pub bag_name : String,
+    pub contents : Vec<(u32, String)>,
+}
+
+fn tokenize(line : &str, line_number : usize) -> Result<Vec<Token<'_>>, ParseError> {
+    let mut tokens : Vec<Token> = Vec::new();
+    let mut chars = line.char_indices().enumerate().peekable();
+
+    while let Some((index, (start, characther))) = chars.next() {
+        let column = index + 1;
+        let kind = match characther {
+            _ if characther.is_whitespace() => continue,
+            ',' => TokenKind::Comma,
+            '.' => TokenKind::Period,
+            _ => {
+                // Words and numbers run until whitespace or punctuation
+                let mut end = start + characther.len_utf8();
+                while let Some(&(_, (next_start, next))) = chars.peek() {
+                    if next.is_whitespace() || next == ',' || next == '.' { break; }
+                    end = next_start + next.len_utf8();
+                    chars.next();
+                }
+
+                let text = &line[start..end];
+                if text.chars().all(|characther| characther.is_ascii_digit()) {
+                    match text.parse() {
+                        Ok(number) => TokenKind::Number(number),
+                        Err(_) => return Err(ParseError { line : line_number, column, message : format!("quantity '{}' is too big", text) }),
+                    }
+                } else {
+                    TokenKind::Word(text)
+                }
+            },
+        };
+
+        tokens.push(Token { kind, column });
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens : Vec<Token<'a>>,
+    position : usize,
+    line_number : usize,
+    // Column just after the end of the line, for errors on missing tokens
+    end_column : usize,
+}
+
+fn is_bag_word(word : &str) -> bool {
+    word == "bag" || word == "bags"
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<Token<'a>> {
+        self.tokens.get(self.position).copied()
+    }
+
+    fn error<T>(&self, expected : &str) -> Result<T, ParseError> {
+        match self.peek() {
+            Some(token) => Err(ParseError { line : self.line_number, column : token.column, message : format!("expected {}, found {}", expected, token.kind) }),
+            None => Err(ParseError { line : self.line_number, column : self.end_column, message : format!("expected {}, found end of line", expected) }),
+        }
+    }
+
+    fn expect_word(&mut self, accept : fn(&str) -> bool, expected : &str) -> Result<(), ParseError> {
+        match self.peek().map(|token| token.kind) {
+            Some(TokenKind::Word(word)) if accept(word) => {
+                self.position += 1;
+                Ok(())
+            },
+            _ => self.error(expected),
+        }
+    }
+
+    // Colour words up to and including the 'bag' or 'bags' keyword
+    fn parse_bag_name(&mut self) -> Result<String, ParseError> {
+        let mut words : Vec<&str> = Vec::new();
+        loop {
+            match self.peek().map(|token| token.kind) {
+                Some(TokenKind::Word(word)) if is_bag_word(word) && !words.is_empty() => {
+                    self.position += 1;
+                    return Ok(words.join(" "));
+                },
+                Some(TokenKind::Word(word)) if !is_bag_word(word) => {
+                    words.push(word);
+                    self.position += 1;
+                },
+                _ if words.is_empty() => return self.error("a bag name"),
+                _ => return self.error("'bag' or 'bags'"),
+            }
+        }
+    }
+
+    fn parse_rule(&mut self) -> Result<RuleLine, ParseError> {
+        let bag_name = self.parse_bag_name()?;
+        self.expect_word(|word| word == "contain", "'contain'")?;
+
+        let mut contents : Vec<(u32, String)> = Vec::new();
+        if let Some(TokenKind::Word("no")) = self.peek().map(|token| token.kind) {
+            self.position += 1;
+            self.expect_word(|word| word == "other", "'other'")?;
+            self.expect_word(is_bag_word, "'bags'")?;
+        } else {
+            loop {
+                let quantity = match self.peek().map(|token| token.kind) {
+                    Some(TokenKind::Number(quantity)) if quantity > 0 => quantity,
+                    Some(TokenKind::Number(_)) => return self.error("a positive quantity"),
+                    _ => return self.error("a quantity or 'no other bags'"),
+                };
+                self.position += 1;
+                contents.push((quantity, self.parse_bag_name()?));
+
+                match self.peek().map(|token| token.kind) {
+                    Some(TokenKind::Comma) => self.position += 1,
+                    _ => break,
+                }
+            }
+        }
+
+        match self.peek().map(|token| token.kind) {
+            Some(TokenKind::Period) => self.position += 1,
+            _ => return self.error("',' or '.'"),
+        }
+        if self.peek().is_some() { return self.error("end of line"); }
+
+        Ok(RuleLine { bag_name, contents })
+    }
+}
+
+pub fn parse_rule(line : &str, line_number : usize) -> Result<RuleLine, ParseError> {
+    let mut parser = Parser {
+        tokens : tokenize(line, line_number)?,
+        position : 0,
+        line_number,
+        end_column : line.chars().count() + 1,
+    };
+
+    parser.parse_rule()
+}
+
+// Canonical text of a rule, parsing it back gives the same rule
+pub fn format_rule(rule : &RuleLine) -> String {
+    if rule.contents.is_empty() {
+        return format!("{} bags contain no other bags.", rule.bag_name);
+    }
+
+    let contents : Vec<String> = rule.contents.iter()
+        .map(|(quantity, bag_name)| format!("{} {} {}", quantity, bag_name, if *quantity == 1 { "bag" } else { "bags" }))
+        .collect();
+    format!("{} bags contain {}.", rule.bag_name, contents.join(", "))
+}
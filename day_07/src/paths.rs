@@ -0,0 +1,141 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::rules::{BagError, BagId, Rules};
+
+pub struct ContainmentPath {
+    pub bags : Vec<BagId>,
+    // Number of the last bag found inside the first one through this path
+    pub quantity : u64,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Visit {
+    New,
+    InPath,
+    Done,
+}
+
+// Bags reachable from the roots (included), containers before contents, failing on a cycle
+fn topological_order(rules : &Rules, roots : &[BagId]) -> Result<Vec<BagId>, BagError> {
+    let bags = rules.bags();
+    let mut visits : Vec<Visit> = vec![Visit::New; bags.len()];
+    let mut order : Vec<BagId> = Vec::new();
+
+    for &root in roots.iter() {
+        if visits[root] != Visit::New { continue; }
+
+        let mut stack : Vec<(BagId, usize)> = vec![(root, 0)];
+        visits[root] = Visit::InPath;
+        while let Some(&(current, next_edge)) = stack.last() {
+            let sub_bags = bags[current].get_sub_bags();
+            if next_edge == sub_bags.len() {
+                visits[current] = Visit::Done;
+                order.push(current);
+                stack.pop();
+                continue;
+            }
+
+            if let Some(last) = stack.last_mut() { last.1 += 1; }
+            let sub_bag = sub_bags[next_edge].get_sub_bag();
+            match visits[sub_bag] {
+                Visit::New => {
+                    visits[sub_bag] = Visit::InPath;
+                    stack.push((sub_bag, 0));
+                },
+                Visit::InPath => return Err(BagError::Cycle(bags[sub_bag].get_bag_name().to_string())),
+                Visit::Done => (),
+            }
+        }
+    }
+
+    order.reverse();
+    Ok(order)
+}
+
+// Every chain of bags going from `outer` down to `inner`, stopping after `limit` paths if given
+pub fn all_paths(rules : &Rules, outer : &str, inner : &str, limit : Option<usize>) -> Result<Vec<ContainmentPath>, BagError> {
+    let outer_id = rules.find_bag(outer)?;
+    let inner_id = rules.find_bag(inner)?;
+
+    // Cycles would make the number of paths infinite
+    topological_order(rules, &[outer_id])?;
+
+    // Only bags that can hold `inner` lead to a path, the others are never walked into
+    let containers = rules.containers_of(inner)?;
+
+    let mut paths : Vec<ContainmentPath> = Vec::new();
+    let mut current : Vec<BagId> = vec![outer_id];
+    collect_paths(rules, inner_id, &containers, &mut current, 1, limit, &mut paths)?;
+    Ok(paths)
+}
+
+fn collect_paths(rules : &Rules, inner : BagId, containers : &BTreeSet<BagId>, current : &mut Vec<BagId>, quantity : u64, limit : Option<usize>, paths : &mut Vec<ContainmentPath>) -> Result<(), BagError> {
+    let last = *current.last().unwrap();
+    if last == inner && current.len() > 1 {
+        paths.push(ContainmentPath { bags : current.clone(), quantity });
+        return Ok(());
+    }
+
+    for contain in rules.bags()[last].get_sub_bags().iter() {
+        if limit.map(|limit| paths.len() >= limit).unwrap_or(false) { return Ok(()); }
+
+        let sub_bag = contain.get_sub_bag();
+        if sub_bag != inner && !containers.contains(&sub_bag) { continue; }
+
+        let sub_quantity = quantity.checked_mul(contain.get_sub_bag_quantity() as u64)
+            .ok_or_else(|| BagError::Overflow(rules.bags()[current[0]].get_bag_name().to_string()))?;
+        current.push(sub_bag);
+        collect_paths(rules, inner, containers, current, sub_quantity, limit, paths)?;
+        current.pop();
+    }
+
+    Ok(())
+}
+
+// Number of each distinct bag found inside `outer`, counting every level of nesting
+pub fn contents_multiset(rules : &Rules, outer : &str) -> Result<BTreeMap<BagId, u64>, BagError> {
+    let outer_id = rules.find_bag(outer)?;
+    let order = topological_order(rules, &[outer_id])?;
+
+    // Each bag is only expanded once all the bags containing it have been counted
+    let mut counts : BTreeMap<BagId, u64> = BTreeMap::new();
+    counts.insert(outer_id, 1);
+    for &bag in order.iter() {
+        let count = counts.get(&bag).copied().unwrap_or(0);
+        for contain in rules.bags()[bag].get_sub_bags().iter() {
+            let entry = counts.entry(contain.get_sub_bag()).or_insert(0);
+            *entry = count.checked_mul(contain.get_sub_bag_quantity() as u64)
+                .and_then(|added| entry.checked_add(added))
+                .ok_or_else(|| BagError::Overflow(outer.to_string()))?;
+        }
+    }
+
+    counts.remove(&outer_id);
+    Ok(counts)
+}
+
+// Longest chain of bags nested inside each other in the whole rule set
+pub fn deepest_chain(rules : &Rules) -> Result<Vec<BagId>, BagError> {
+    let bags = rules.bags();
+
+    let roots : Vec<BagId> = (0..bags.len()).collect();
+
+    // Depth and next bag of the deepest chain starting at each bag,
+    // contents come after their containers so walking backwards sees them first
+    let mut deepest : Vec<(usize, Option<BagId>)> = vec![(1, None); bags.len()];
+    for &bag in topological_order(rules, &roots)?.iter().rev() {
+        let best = bags[bag].get_sub_bags().iter()
+            .map(|contain| (deepest[contain.get_sub_bag()].0, contain.get_sub_bag()))
+            .max_by_key(|&(depth, _)| depth);
+        if let Some((depth, next)) = best { deepest[bag] = (depth + 1, Some(next)); }
+    }
+
+    let mut current = (0..bags.len()).max_by_key(|&bag| deepest[bag].0);
+    let mut chain : Vec<BagId> = Vec::new();
+    while let Some(bag) = current {
+        chain.push(bag);
+        current = deepest[bag].1;
+    }
+
+    Ok(chain)
+}
@@ -0,0 +1,205 @@
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fmt;
+use std::rc::Rc;
+
+pub type BagId = usize;
+
+#[derive(Debug, PartialEq)]
+pub enum BagError {
+    UnknownBag(String),
+    Cycle(String),
+    // Number of bags inside the named one does not fit in a u64
+    Overflow(String),
+}
+
+impl fmt::Display for BagError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BagError::UnknownBag(bag_name) => write!(f, "No rule found for bag '{}'", bag_name),
+            BagError::Cycle(bag_name) => write!(f, "Bag '{}' ends up containing itself", bag_name),
+            BagError::Overflow(bag_name) => write!(f, "Too many bags inside '{}' to be counted", bag_name),
+        }
+    }
+}
+
+pub struct Bag {
+    bag_name : String,
+    sub_bags : Vec<ContainedBag>,
+    // Lines of every rule defining this bag, empty if it is only ever referenced
+    definitions : Vec<usize>,
+}
+
+impl Bag {
+    pub fn get_bag_name(&self) -> &str {
+        &self.bag_name
+    }
+
+    pub fn get_sub_bags(&self) -> &[ContainedBag] {
+        &self.sub_bags
+    }
+
+    pub fn get_definitions(&self) -> &[usize] {
+        &self.definitions
+    }
+
+    pub fn is_declared(&self) -> bool {
+        !self.definitions.is_empty()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ContainedBag {
+    quantity : u32,
+    bag_ref : BagId,
+}
+
+impl ContainedBag {
+    pub fn get_sub_bag_quantity(&self) -> u32 {
+        self.quantity
+    }
+
+    pub fn get_sub_bag(&self) -> BagId {
+        self.bag_ref
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Memo {
+    InProgress,
+    Done(u64),
+}
+
+pub struct Rules {
+    bags : Vec<Bag>,
+    index : HashMap<String, BagId>,
+    // Reverse adjacency: bags that directly contain each bag
+    containers : Vec<Vec<BagId>>,
+    // Query caches, cleared whenever a rule changes
+    containers_memo : RefCell<HashMap<BagId, Rc<BTreeSet<BagId>>>>,
+    contents_memo : RefCell<HashMap<BagId, Memo>>,
+}
+
+impl Rules {
+    pub fn new() -> Rules {
+        Rules {
+            bags : Vec::new(),
+            index : HashMap::new(),
+            containers : Vec::new(),
+            containers_memo : RefCell::new(HashMap::new()),
+            contents_memo : RefCell::new(HashMap::new()),
+        }
+    }
+
+    fn clear_memo(&mut self) {
+        self.containers_memo.get_mut().clear();
+        self.contents_memo.get_mut().clear();
+    }
+
+    fn intern(&mut self, bag_name : &str) -> BagId {
+        if let Some(&id) = self.index.get(bag_name) { return id; }
+
+        let id = self.bags.len();
+        self.bags.push(Bag { bag_name : bag_name.to_string(), sub_bags : Vec::new(), definitions : Vec::new() });
+        self.containers.push(Vec::new());
+        self.index.insert(bag_name.to_string(), id);
+        id
+    }
+
+    pub fn add_bag(&mut self, bag_name : &str, line : usize) -> BagId {
+        let id = self.intern(bag_name);
+        self.bags[id].definitions.push(line);
+        id
+    }
+
+    pub fn add_contain_to_bag(&mut self, bag : BagId, quantity : u32, sub_bag_name : &str) {
+        let sub_bag = self.intern(sub_bag_name);
+        self.bags[bag].sub_bags.push(ContainedBag { quantity, bag_ref : sub_bag });
+        self.containers[sub_bag].push(bag);
+        self.clear_memo();
+    }
+
+    pub fn bags(&self) -> &[Bag] {
+        &self.bags
+    }
+
+    pub fn direct_containers(&self, id : BagId) -> &[BagId] {
+        &self.containers[id]
+    }
+
+    pub fn find_bag(&self, bag_name : &str) -> Result<BagId, BagError> {
+        self.index.get(bag_name).copied().ok_or_else(|| BagError::UnknownBag(bag_name.to_string()))
+    }
+
+    // Every bag that ends up (directly or not) containing the given one
+    pub fn containers_of(&self, bag_name : &str) -> Result<Rc<BTreeSet<BagId>>, BagError> {
+        let id = self.find_bag(bag_name)?;
+        if let Some(cached) = self.containers_memo.borrow().get(&id) { return Ok(Rc::clone(cached)); }
+
+        let mut found : BTreeSet<BagId> = BTreeSet::new();
+        let mut to_explore : VecDeque<BagId> = VecDeque::from(vec![id]);
+        while let Some(current) = to_explore.pop_front() {
+            for &container in self.containers[current].iter() {
+                if found.insert(container) { to_explore.push_back(container); }
+            }
+        }
+
+        let found = Rc::new(found);
+        self.containers_memo.borrow_mut().insert(id, Rc::clone(&found));
+        Ok(found)
+    }
+
+    // Every distinct bag found (directly or not) inside the given one
+    pub fn contents_of(&self, bag_name : &str) -> Result<BTreeSet<BagId>, BagError> {
+        let id = self.find_bag(bag_name)?;
+
+        let mut found : BTreeSet<BagId> = BTreeSet::new();
+        let mut to_explore : VecDeque<BagId> = VecDeque::from(vec![id]);
+        while let Some(current) = to_explore.pop_front() {
+            for contain in self.bags[current].sub_bags.iter() {
+                if found.insert(contain.bag_ref) { to_explore.push_back(contain.bag_ref); }
+            }
+        }
+
+        Ok(found)
+    }
+
+    // Number of bags inside the given one, counting every level of nesting
+    pub fn total_contents(&self, bag_name : &str) -> Result<u64, BagError> {
+        let id = self.find_bag(bag_name)?;
+        self.total_contents_of(id)
+    }
+
+    fn total_contents_of(&self, id : BagId) -> Result<u64, BagError> {
+        match self.contents_memo.borrow().get(&id) {
+            Some(Memo::Done(total)) => return Ok(*total),
+            Some(Memo::InProgress) => return Err(BagError::Cycle(self.bags[id].bag_name.clone())),
+            None => (),
+        }
+
+        if !self.bags[id].is_declared() { return Err(BagError::UnknownBag(self.bags[id].bag_name.clone())); }
+
+        self.contents_memo.borrow_mut().insert(id, Memo::InProgress);
+        let mut total : u64 = 0;
+        for contain in self.bags[id].sub_bags.iter() {
+            let inside = match self.total_contents_of(contain.bag_ref) {
+                Ok(inside) => inside,
+                Err(error) => {
+                    self.contents_memo.borrow_mut().remove(&id);
+                    return Err(error);
+                },
+            };
+            let added = inside.checked_add(1).and_then(|bags| bags.checked_mul(contain.quantity as u64));
+            match added.and_then(|added| total.checked_add(added)) {
+                Some(new_total) => total = new_total,
+                None => {
+                    self.contents_memo.borrow_mut().remove(&id);
+                    return Err(BagError::Overflow(self.bags[id].bag_name.clone()));
+                },
+            }
+        }
+
+        self.contents_memo.borrow_mut().insert(id, Memo::Done(total));
+        Ok(total)
+    }
+}
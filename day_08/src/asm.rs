@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::isa::{self, OperandKind, Operation};
+
+#[derive(Debug, PartialEq)]
+pub struct AsmError {
+    pub line : usize,
+    pub message : String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+
+struct SourceInstruction<'a> {
+    line : usize,
+    operation : &'static Operation,
+    arguments : Vec<&'a str>,
+}
+
+fn strip_comment(line : &str) -> &str {
+    match line.find([';', '#']) {
+        Some(start) => &line[..start],
+        None => line,
+    }
+}
+
+fn is_label(name : &str) -> bool {
+    let mut characthers = name.chars();
+    match characthers.next() {
+        Some(first) if first.is_ascii_alphabetic() || first == '_' => characthers.all(|characther| characther.is_ascii_alphanumeric() || characther == '_'),
+        _ => false,
+    }
+}
+
+// Turns a source with labels ('name:') and comments (';' or '#') into the 'op ±n' program format,
+// jump offsets may be written as a label name and become relative to their instruction
+pub fn assemble(source : &str, default_isa : u32) -> Result<String, AsmError> {
+    let mut isa_version : u32 = default_isa;
+    let mut declared_isa : bool = false;
+    let mut labels : HashMap<&str, (usize, usize)> = HashMap::new();
+    let mut instructions : Vec<SourceInstruction> = Vec::new();
+
+    for (index_line, raw_line) in source.lines().enumerate() {
+        let line_number = index_line + 1;
+        let error = |message : String| AsmError { line : line_number, message };
+        let mut line = strip_comment(raw_line).trim();
+        if line.is_empty() { continue; }
+
+        if let Some(version) = isa::parse_directive(line) {
+            if declared_isa || !instructions.is_empty() || !labels.is_empty() {
+                return Err(error(String::from("'.isa' must come before any instruction")));
+            }
+            isa_version = version.map_err(error)?;
+            declared_isa = true;
+            continue;
+        }
+
+        // Any number of labels may precede the instruction
+        while let Some(colon) = line.find(':') {
+            let name = line[..colon].trim();
+            if !is_label(name) { return Err(error(format!("Invalid label '{}'", name))); }
+            if let Some((_, previous_line)) = labels.insert(name, (instructions.len(), line_number)) {
+                return Err(error(format!("Label '{}' already defined on line {}", name, previous_line)));
+            }
+            line = line[colon + 1..].trim();
+        }
+        if line.is_empty() { continue; }
+
+        let words : Vec<&str> = line.split_whitespace().collect();
+        let operation = isa::find_operation(words[0], isa_version).map_err(|message| error(format!("'{}': {}", words[0], message)))?;
+        if words.len() - 1 != operation.operands.len() {
+            return Err(error(format!("'{}' takes {} argument(s), found {}", operation.name, operation.operands.len(), words.len() - 1)));
+        }
+
+        instructions.push(SourceInstruction { line : line_number, operation, arguments : words[1..].to_vec() });
+    }
+
+    let mut output = String::new();
+    if declared_isa || isa_version != isa::DEFAULT_ISA { output.push_str(&format!(".isa {}\n", isa_version)); }
+
+    for (index, instruction) in instructions.iter().enumerate() {
+        let error = |message : String| AsmError { line : instruction.line, message };
+
+        let arguments : Vec<String> = instruction.operation.operands.iter().zip(instruction.arguments.iter())
+            .map(|(&kind, &argument)| match (kind, labels.get(argument)) {
+                (OperandKind::Offset, Some(&(target, _))) => Ok(format!("{:+}", target as i64 - index as i64)),
+                (OperandKind::Offset, None) if is_label(argument) => Err(error(format!("Unknown label '{}'", argument))),
+                _ => Ok(argument.to_string()),
+            })
+            .collect::<Result<_, _>>()?;
+
+        // Parsing the resolved arguments both validates them and gives the canonical spelling
+        let argument_slices : Vec<&str> = arguments.iter().map(|argument| argument.as_str()).collect();
+        let operands = isa::parse_operands(instruction.operation, &argument_slices)
+            .map_err(|message| error(format!("'{}': {}", instruction.operation.name, message)))?;
+
+        output.push_str(instruction.operation.name);
+        for operand in operands.iter() {
+            output.push_str(&format!(" {}", operand));
+        }
+        output.push('\n');
+    }
+
+    Ok(output)
+}
@@ -0,0 +1,377 @@
+use std::collections::VecDeque;
+use std::fs;
+
+use crate::isa::{self, Flow};
+use crate::Machine;
+
+const UNREACHABLE_COLOUR : &str = "lightgrey";
+const LOOP_COLOUR : &str = "salmon";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Exit {
+    Block(usize),
+    // Just after the last instruction, or a 'hlt'
+    End,
+    // Anywhere else outside of the program, the machine stops with an error
+    Outside,
+}
+
+pub struct Edge {
+    pub exit : Exit,
+    pub label : &'static str,
+}
+
+pub struct Block {
+    // Instructions from `start` up to, but not including, `end`
+    pub start : usize,
+    pub end : usize,
+    pub edges : Vec<Edge>,
+    // None when an instruction changes the accumulator by an amount only known when running
+    pub accumulator_delta : Option<i64>,
+    pub reachable : bool,
+    pub in_infinite_loop : bool,
+}
+
+pub struct Loop {
+    pub blocks : Vec<usize>,
+    // No path leaves the loop towards the end of the program
+    pub infinite : bool,
+}
+
+pub struct Graph {
+    pub blocks : Vec<Block>,
+    pub loops : Vec<Loop>,
+    // Block holding each instruction
+    block_of : Vec<usize>,
+}
+
+impl Graph {
+    pub fn get_block_of(&self, index : usize) -> &Block {
+        &self.blocks[self.block_of[index]]
+    }
+
+    pub fn unreachable_instructions(&self) -> Vec<usize> {
+        self.blocks.iter()
+            .filter(|block| !block.reachable)
+            .flat_map(|block| block.start..block.end)
+            .collect()
+    }
+}
+
+fn exit_of(pointer : i64, block_of : &[usize]) -> Exit {
+    if pointer == block_of.len() as i64 { Exit::End }
+    else if pointer >= 0 && pointer < block_of.len() as i64 { Exit::Block(block_of[pointer as usize]) }
+    else { Exit::Outside }
+}
+
+fn edge_label(flow : Flow, position : usize) -> &'static str {
+    match (flow, position) {
+        (Flow::Next, _) => "next",
+        (Flow::Jump, _) => "jump",
+        (Flow::Branch, 0) => "not taken",
+        (Flow::Branch, _) => "taken",
+        (Flow::Halt, _) => "halt",
+    }
+}
+
+// Strongly connected components of the reachable blocks, only keeping the ones with a cycle
+fn find_cycles(blocks : &[Block]) -> Vec<Vec<usize>> {
+    struct Tarjan<'a> {
+        blocks : &'a [Block],
+        index : Vec<Option<usize>>,
+        low : Vec<usize>,
+        on_stack : Vec<bool>,
+        stack : Vec<usize>,
+        next_index : usize,
+        cycles : Vec<Vec<usize>>,
+    }
+
+    impl Tarjan<'_> {
+        fn open(&mut self, block : usize) {
+            self.index[block] = Some(self.next_index);
+            self.low[block] = self.next_index;
+            self.next_index += 1;
+            self.stack.push(block);
+            self.on_stack[block] = true;
+        }
+
+        // Pops the component rooted at `block` once all of its edges have been followed
+        fn close(&mut self, block : usize) {
+            if Some(self.low[block]) != self.index[block] { return; }
+
+            let mut component : Vec<usize> = Vec::new();
+            while let Some(member) = self.stack.pop() {
+                self.on_stack[member] = false;
+                component.push(member);
+                if member == block { break; }
+            }
+
+            let loops_on_itself = self.blocks[block].edges.iter().any(|edge| edge.exit == Exit::Block(block));
+            if component.len() > 1 || loops_on_itself {
+                component.sort_unstable();
+                self.cycles.push(component);
+            }
+        }
+
+        // Depth first search with an explicit stack of (block, next edge), long chains of blocks can't overflow
+        fn visit(&mut self, root : usize) {
+            self.open(root);
+            let mut calls : Vec<(usize, usize)> = vec![(root, 0)];
+
+            while let Some(&(block, next_edge)) = calls.last() {
+                let edges = &self.blocks[block].edges;
+                if next_edge == edges.len() {
+                    calls.pop();
+                    self.close(block);
+                    if let Some(&(parent, _)) = calls.last() { self.low[parent] = self.low[parent].min(self.low[block]); }
+                    continue;
+                }
+
+                if let Some(last) = calls.last_mut() { last.1 += 1; }
+                let next = match edges[next_edge].exit {
+                    Exit::Block(next) => next,
+                    _ => continue,
+                };
+
+                match self.index[next] {
+                    None => {
+                        self.open(next);
+                        calls.push((next, 0));
+                    },
+                    Some(index) if self.on_stack[next] => self.low[block] = self.low[block].min(index),
+                    Some(_) => (),
+                }
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        blocks,
+        index : vec![None; blocks.len()],
+        low : vec![0; blocks.len()],
+        on_stack : vec![false; blocks.len()],
+        stack : Vec::new(),
+        next_index : 0,
+        cycles : Vec::new(),
+    };
+
+    for (block, content) in blocks.iter().enumerate() {
+        if content.reachable && tarjan.index[block].is_none() { tarjan.visit(block); }
+    }
+
+    tarjan.cycles.sort();
+    tarjan.cycles
+}
+
+pub fn analyse(machine : &Machine) -> Graph {
+    let instructions = machine.get_instructions();
+    let length = instructions.len();
+
+    // A block starts at the first instruction, at every jump target and after every jump or halt
+    let mut leaders : Vec<bool> = vec![false; length + 1];
+    if length > 0 { leaders[0] = true; }
+    for (index, instruction) in instructions.iter().enumerate() {
+        let operation = instruction.get_operation();
+        if operation.flow == Flow::Next { continue; }
+
+        leaders[index + 1] = true;
+        for pointer in isa::successors(operation, instruction.get_operands(), index as i64) {
+            if pointer >= 0 && pointer < length as i64 { leaders[pointer as usize] = true; }
+        }
+    }
+
+    let starts : Vec<usize> = (0..length).filter(|&index| leaders[index]).collect();
+    let mut block_of : Vec<usize> = vec![0; length];
+    for (block, &start) in starts.iter().enumerate() {
+        let end = starts.get(block + 1).copied().unwrap_or(length);
+        for slot in block_of[start..end].iter_mut() { *slot = block; }
+    }
+
+    let mut blocks : Vec<Block> = starts.iter().enumerate().map(|(block, &start)| {
+        let end = starts.get(block + 1).copied().unwrap_or(length);
+        let last = &instructions[end - 1];
+        let flow = last.get_operation().flow;
+
+        let edges : Vec<Edge> = match flow {
+            Flow::Halt => vec![Edge { exit : Exit::End, label : edge_label(flow, 0) }],
+            _ => isa::successors(last.get_operation(), last.get_operands(), (end - 1) as i64).iter().enumerate()
+                .map(|(position, &pointer)| Edge { exit : exit_of(pointer, &block_of), label : edge_label(flow, position) })
+                .collect(),
+        };
+
+        let accumulator_delta : Option<i64> = instructions[start..end].iter()
+            .map(|instruction| isa::accumulator_delta(instruction.get_operation(), instruction.get_operands()).map(|delta| delta as i64))
+            .sum();
+
+        Block { start, end, edges, accumulator_delta, reachable : false, in_infinite_loop : false }
+    }).collect();
+
+    let mut queue : VecDeque<usize> = VecDeque::new();
+    if !blocks.is_empty() {
+        blocks[0].reachable = true;
+        queue.push_back(0);
+    }
+    while let Some(block) = queue.pop_front() {
+        let nexts : Vec<usize> = blocks[block].edges.iter()
+            .filter_map(|edge| match edge.exit { Exit::Block(next) => Some(next), _ => None })
+            .collect();
+        for next in nexts {
+            if blocks[next].reachable { continue; }
+            blocks[next].reachable = true;
+            queue.push_back(next);
+        }
+    }
+
+    // Blocks with a path to the end, walking the edges backwards
+    let mut predecessors : Vec<Vec<usize>> = vec![Vec::new(); blocks.len()];
+    let mut can_end : Vec<bool> = vec![false; blocks.len()];
+    for (block, content) in blocks.iter().enumerate() {
+        for edge in content.edges.iter() {
+            match edge.exit {
+                Exit::Block(next) => predecessors[next].push(block),
+                Exit::End => can_end[block] = true,
+                Exit::Outside => (),
+            }
+        }
+    }
+    let mut queue : VecDeque<usize> = (0..blocks.len()).filter(|&block| can_end[block]).collect();
+    while let Some(block) = queue.pop_front() {
+        for &predecessor in predecessors[block].iter() {
+            if can_end[predecessor] { continue; }
+            can_end[predecessor] = true;
+            queue.push_back(predecessor);
+        }
+    }
+
+    let loops : Vec<Loop> = find_cycles(&blocks).into_iter()
+        .map(|cycle| {
+            let infinite = cycle.iter().all(|&block| !can_end[block]);
+            Loop { blocks : cycle, infinite }
+        })
+        .collect();
+
+    for cycle in loops.iter().filter(|cycle| cycle.infinite) {
+        for &block in cycle.blocks.iter() { blocks[block].in_infinite_loop = true; }
+    }
+
+    Graph { blocks, loops, block_of }
+}
+
+fn describe_exit(graph : &Graph, exit : Exit) -> String {
+    match exit {
+        Exit::Block(block) => format!("{}", graph.blocks[block].start),
+        Exit::End => String::from("end"),
+        Exit::Outside => String::from("outside"),
+    }
+}
+
+fn describe_delta(delta : Option<i64>) -> String {
+    match delta {
+        Some(delta) => format!("{:+}", delta),
+        None => String::from("?"),
+    }
+}
+
+fn describe_range(block : &Block) -> String {
+    if block.end - block.start == 1 { format!("{}", block.start) } else { format!("{}-{}", block.start, block.end - 1) }
+}
+
+pub fn print_summary(graph : &Graph) {
+    println!("Basic blocks: {}", graph.blocks.len());
+    for (block, content) in graph.blocks.iter().enumerate() {
+        let edges : Vec<String> = content.edges.iter()
+            .map(|edge| format!("{} {}", edge.label, describe_exit(graph, edge.exit)))
+            .collect();
+        println!("\tblock {} [{}]: acc {}, {}{}", block, describe_range(content), describe_delta(content.accumulator_delta),
+            edges.join(", "), if content.reachable { "" } else { " (unreachable)" });
+    }
+
+    println!("Loops: {}", graph.loops.len());
+    for cycle in graph.loops.iter() {
+        let ranges : Vec<String> = cycle.blocks.iter().map(|&block| describe_range(&graph.blocks[block])).collect();
+        println!("\t{} [{}]", if cycle.infinite { "infinite" } else { "exits" }, ranges.join(", "));
+    }
+
+    let unreachable = graph.unreachable_instructions();
+    println!("Unreachable instructions: {}", unreachable.len());
+}
+
+// Instructions grouped by block, with their jump targets and what the analysis found about them
+pub fn disassemble(machine : &Machine, graph : &Graph) -> String {
+    let mut output = String::new();
+
+    for (index, instruction) in machine.get_instructions().iter().enumerate() {
+        let block = graph.get_block_of(index);
+        if block.start == index {
+            let edges : Vec<String> = block.edges.iter()
+                .map(|edge| format!("{} {}", edge.label, describe_exit(graph, edge.exit)))
+                .collect();
+            output.push_str(&format!("; block {} [{}], acc {}, {}\n", graph.block_of[index], describe_range(block),
+                describe_delta(block.accumulator_delta), edges.join(", ")));
+        }
+
+        let operation = instruction.get_operation();
+        let target : String = match operation.flow {
+            Flow::Jump | Flow::Branch => {
+                let pointer = *isa::successors(operation, instruction.get_operands(), index as i64).last().unwrap();
+                let exit = exit_of(pointer, &graph.block_of);
+                match exit {
+                    Exit::Block(_) => format!("-> {}", pointer),
+                    _ => format!("-> {}", describe_exit(graph, exit)),
+                }
+            },
+            _ => String::new(),
+        };
+
+        let mut notes : Vec<&str> = Vec::new();
+        if !block.reachable { notes.push("unreachable"); }
+        if block.in_infinite_loop { notes.push("infinite loop"); }
+
+        let line = format!("{:>5}: {:<12} {:<12} {}", index, instruction.to_string(), target, notes.join(", "));
+        output.push_str(line.trim_end());
+        output.push('\n');
+    }
+
+    output
+}
+
+fn block_label(machine : &Machine, block : &Block) -> String {
+    let mut label = format!("{}: acc {}\\l", describe_range(block), describe_delta(block.accumulator_delta));
+    for index in block.start..block.end {
+        label.push_str(&format!("{}: {}\\l", index, machine.get_instructions()[index]));
+    }
+    label
+}
+
+pub fn to_dot(machine : &Machine, graph : &Graph) -> String {
+    let mut output = String::from("digraph program {\n    node [shape=box, style=filled, fillcolor=white, fontname=monospace];\n");
+    output.push_str("    end [shape=doublecircle];\n");
+
+    let outside = graph.blocks.iter().any(|block| block.edges.iter().any(|edge| edge.exit == Exit::Outside));
+    if outside { output.push_str("    outside [shape=octagon];\n"); }
+
+    for (block, content) in graph.blocks.iter().enumerate() {
+        let colour = if !content.reachable { UNREACHABLE_COLOUR }
+            else if content.in_infinite_loop { LOOP_COLOUR }
+            else { "white" };
+        output.push_str(&format!("    b{} [label=\"{}\", fillcolor={}];\n", block, block_label(machine, content), colour));
+    }
+
+    for (block, content) in graph.blocks.iter().enumerate() {
+        for edge in content.edges.iter() {
+            let target = match edge.exit {
+                Exit::Block(next) => format!("b{}", next),
+                Exit::End => String::from("end"),
+                Exit::Outside => String::from("outside"),
+            };
+            output.push_str(&format!("    b{} -> {} [label=\"{}\"];\n", block, target, edge.label));
+        }
+    }
+
+    output.push_str("}\n");
+    output
+}
+
+pub fn write_dot(filename : &str, machine : &Machine, graph : &Graph) -> Result<(), String> {
+    fs::write(filename, to_dot(machine, graph)).map_err(|error| format!("Could not write '{}': {}", filename, error))
+}
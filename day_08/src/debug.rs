@@ -0,0 +1,275 @@
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+
+use crate::{Machine, Snapshot};
+
+const LIST_RADIUS : usize = 3;
+
+const HELP : &str = "Commands:
+    step [n]            (s) run n instructions, 1 by default
+    continue            (c) run until a breakpoint, a loop or the end of the program
+    break <index>       (b) stop before running the instruction at index
+    break acc <value>       stop once the accumulator changes to value
+    delete <index>          remove an instruction breakpoint
+    delete acc <value>      remove an accumulator breakpoint
+    watch               (w) toggle printing every accumulator change
+    executed            (e) show the instructions already executed
+    rewind [n]          (r) undo the last n instructions, 1 by default
+    list [n]            (l) show the instructions around the pointer
+    info                (i) show the machine state and breakpoints
+    reset                   go back to the start of the program
+    quit                (q) leave the debugger";
+
+enum StepResult {
+    Ran,
+    Finished,
+    // Running the next instruction a second time would never end
+    Loop,
+    Failed(&'static str),
+}
+
+pub struct Debugger<'a> {
+    machine : &'a mut Machine,
+    history : Vec<Snapshot>,
+    pointer_breakpoints : BTreeSet<u32>,
+    accumulator_breakpoints : BTreeSet<i32>,
+    watch : bool,
+    // Steps 'continue' may run, programs with branches can loop without repeating an executed instruction
+    max_steps : usize,
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(machine : &'a mut Machine, max_steps : usize) -> Debugger<'a> {
+        machine.reset_machine();
+
+        Debugger {
+            machine,
+            history : Vec::new(),
+            pointer_breakpoints : BTreeSet::new(),
+            accumulator_breakpoints : BTreeSet::new(),
+            watch : false,
+            max_steps,
+        }
+    }
+
+    fn step(&mut self) -> StepResult {
+        if self.machine.is_finished() { return StepResult::Finished; }
+
+        let snapshot : Snapshot = self.machine.snapshot();
+        let accumulator = self.machine.get_accumulator();
+        match self.machine.step_machine() {
+            Ok(()) => (),
+            Err("Instruction already executed") => return StepResult::Loop,
+            Err(error) => return StepResult::Failed(error),
+        }
+
+        if self.watch && accumulator != self.machine.get_accumulator() {
+            println!("acc: {} -> {} (at {})", accumulator, self.machine.get_accumulator(), snapshot.pointer);
+        }
+        self.history.push(snapshot);
+
+        if self.machine.is_finished() { StepResult::Finished } else { StepResult::Ran }
+    }
+
+    // Prints why the machine stopped, returns false when it can't go any further
+    fn report(&self, result : &StepResult) -> bool {
+        match result {
+            StepResult::Ran => true,
+            StepResult::Finished => {
+                println!("Program finished, accumulator: {}", self.machine.get_accumulator());
+                false
+            },
+            StepResult::Loop => {
+                println!("Instruction {} was already executed, the program loops (accumulator: {})",
+                    self.machine.get_current_pointer(), self.machine.get_accumulator());
+                false
+            },
+            StepResult::Failed(error) => {
+                println!("Error at {}: {}", self.machine.get_current_pointer(), error);
+                false
+            },
+        }
+    }
+
+    fn step_many(&mut self, count : usize) {
+        for _ in 0..count {
+            let result = self.step();
+            if !self.report(&result) { return; }
+        }
+        self.print_current();
+    }
+
+    fn continue_running(&mut self) {
+        for steps in 1.. {
+            if steps > self.max_steps {
+                println!("Stopped after {} steps without reaching a breakpoint or the end", self.max_steps);
+                break;
+            }
+
+            let previous_accumulator = self.machine.get_accumulator();
+            let result = self.step();
+            if !self.report(&result) { return; }
+
+            // Only a change to the value stops, an accumulator already holding it doesn't
+            let accumulator = self.machine.get_accumulator();
+            if accumulator != previous_accumulator && self.accumulator_breakpoints.contains(&accumulator) {
+                println!("Accumulator breakpoint: acc = {}", accumulator);
+                break;
+            }
+            if self.pointer_breakpoints.contains(&self.machine.get_current_pointer()) {
+                println!("Breakpoint at {}", self.machine.get_current_pointer());
+                break;
+            }
+        }
+        self.print_current();
+    }
+
+    fn rewind(&mut self, count : usize) {
+        let mut rewound : usize = 0;
+        while rewound < count {
+            match self.history.pop() {
+                Some(snapshot) => self.machine.rewind_machine(&snapshot),
+                None => break,
+            }
+            rewound += 1;
+        }
+
+        println!("Rewound {} instruction(s)", rewound);
+        self.print_current();
+    }
+
+    fn print_current(&self) {
+        let pointer = self.machine.get_current_pointer();
+        match self.machine.get_instructions().get(pointer as usize) {
+            Some(instruction) => println!("{:>5}: {}    acc = {}", pointer, instruction, self.machine.get_accumulator()),
+            None => println!("{:>5}: <end>    acc = {}", pointer, self.machine.get_accumulator()),
+        }
+    }
+
+    fn list(&self, radius : usize) {
+        let pointer = self.machine.get_current_pointer() as usize;
+        let instructions = self.machine.get_instructions();
+        let start = pointer.saturating_sub(radius);
+        let end = (pointer + radius + 1).min(instructions.len());
+
+        // '>' marks the pointer, '*' a breakpoint and 'x' an executed instruction
+        for (index, instruction) in instructions.iter().enumerate().take(end).skip(start) {
+            println!("{}{}{} {:>5}: {}",
+                if index == pointer { '>' } else { ' ' },
+                if self.pointer_breakpoints.contains(&(index as u32)) { '*' } else { ' ' },
+                if instruction.is_executed() { 'x' } else { ' ' },
+                index, instruction);
+        }
+    }
+
+    fn print_executed(&self) {
+        let executed : Vec<String> = self.machine.get_instructions().iter().enumerate()
+            .filter(|(_, instruction)| instruction.is_executed())
+            .map(|(index, _)| index.to_string())
+            .collect();
+
+        println!("Executed instructions: {}", executed.len());
+        if !executed.is_empty() { println!("\t{}", executed.join(", ")); }
+    }
+
+    fn print_info(&self) {
+        self.print_current();
+        println!("Steps: {}", self.history.len());
+        if self.machine.get_isa() > 1 {
+            println!("Registers: {:?}", self.machine.get_registers());
+            println!("Output: {:?}", self.machine.get_output());
+        }
+        println!("Watch: {}", if self.watch { "on" } else { "off" });
+        println!("Instruction breakpoints: {:?}", self.pointer_breakpoints);
+        println!("Accumulator breakpoints: {:?}", self.accumulator_breakpoints);
+    }
+
+    // Updates a breakpoint set from 'break'/'delete' arguments, with an optional 'acc' prefix
+    fn edit_breakpoint(&mut self, words : &[&str], add : bool) -> Result<(), String> {
+        match words {
+            ["acc", value] => {
+                let value : i32 = value.parse().map_err(|_| format!("Invalid accumulator value '{}'", value))?;
+                if add { self.accumulator_breakpoints.insert(value); } else { self.accumulator_breakpoints.remove(&value); }
+            },
+            [index] => {
+                let index : u32 = index.parse().map_err(|_| format!("Invalid instruction index '{}'", index))?;
+                if index as usize >= self.machine.get_instructions().len() {
+                    return Err(format!("Instruction index {} is outside of the program", index));
+                }
+                if add { self.pointer_breakpoints.insert(index); } else { self.pointer_breakpoints.remove(&index); }
+            },
+            _ => return Err(String::from("Expected an instruction index or 'acc <value>'")),
+        }
+
+        Ok(())
+    }
+
+    // Runs a single command line, returns false when the debugger should exit
+    fn execute(&mut self, line : &str) -> bool {
+        let words : Vec<&str> = line.split_whitespace().collect();
+        let count = |default : usize| -> Result<usize, String> {
+            match words.get(1) {
+                Some(word) => word.parse().map_err(|_| format!("Invalid count '{}'", word)),
+                None => Ok(default),
+            }
+        };
+
+        let result : Result<(), String> = match words.first().copied() {
+            None => Ok(()),
+            Some("step") | Some("s") => count(1).map(|count| self.step_many(count)),
+            Some("continue") | Some("c") => {
+                self.continue_running();
+                Ok(())
+            },
+            Some("break") | Some("b") => self.edit_breakpoint(&words[1..], true),
+            Some("delete") => self.edit_breakpoint(&words[1..], false),
+            Some("watch") | Some("w") => {
+                self.watch = !self.watch;
+                println!("Watch {}", if self.watch { "on" } else { "off" });
+                Ok(())
+            },
+            Some("executed") | Some("e") => {
+                self.print_executed();
+                Ok(())
+            },
+            Some("rewind") | Some("r") => count(1).map(|count| self.rewind(count)),
+            Some("list") | Some("l") => count(LIST_RADIUS).map(|radius| self.list(radius)),
+            Some("info") | Some("i") => {
+                self.print_info();
+                Ok(())
+            },
+            Some("reset") => {
+                self.machine.reset_machine();
+                self.history.clear();
+                self.print_current();
+                Ok(())
+            },
+            Some("help") | Some("h") => {
+                println!("{}", HELP);
+                Ok(())
+            },
+            Some("quit") | Some("q") => return false,
+            Some(other) => Err(format!("Unknown command '{}', 'help' lists the commands", other)),
+        };
+
+        if let Err(error) = result { println!("{}", error); }
+        true
+    }
+
+    pub fn run(&mut self) {
+        println!("{} instructions loaded, 'help' lists the commands", self.machine.get_instructions().len());
+        self.print_current();
+
+        let stdin = io::stdin();
+        loop {
+            print!("(debug) ");
+            io::stdout().flush().unwrap();
+
+            let mut line = String::new();
+            match stdin.lock().read_line(&mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => if !self.execute(line.trim()) { break; },
+            }
+        }
+    }
+}
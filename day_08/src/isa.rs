@@ -0,0 +1,247 @@
+use std::fmt;
+
+// Version 1 is the original boot code instruction set, version 2 adds registers, branches and I/O
+pub const DEFAULT_ISA : u32 = 1;
+pub const LATEST_ISA : u32 = 2;
+
+pub const REGISTERS : usize = 4;
+// Register 'a' is the accumulator used by 'acc'
+pub const ACCUMULATOR : usize = 0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OperandKind {
+    // Signed value
+    Immediate,
+    // Signed jump distance, relative to the instruction
+    Offset,
+    // Register name, 'a' to 'd'
+    Register,
+    // Register name or signed value
+    Source,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operand {
+    Immediate(i32),
+    Register(usize),
+}
+
+impl Operand {
+    fn parse(text : &str, kind : OperandKind) -> Result<Operand, &'static str> {
+        let register = match text.as_bytes() {
+            [letter @ b'a'..=b'z'] if ((letter - b'a') as usize) < REGISTERS => Some((letter - b'a') as usize),
+            _ => None,
+        };
+
+        match (kind, register) {
+            (OperandKind::Register, Some(register)) | (OperandKind::Source, Some(register)) => Ok(Operand::Register(register)),
+            (OperandKind::Register, None) => Err("Expected a register"),
+            (_, _) => text.parse().map(Operand::Immediate).map_err(|_| "Expected a number"),
+        }
+    }
+
+    fn value(&self, cpu : &Cpu) -> i32 {
+        match *self {
+            Operand::Immediate(value) => value,
+            Operand::Register(register) => cpu.registers[register],
+        }
+    }
+
+    fn register(&self) -> usize {
+        match *self {
+            Operand::Register(register) => register,
+            Operand::Immediate(_) => unreachable!("Register operands are checked when parsing"),
+        }
+    }
+}
+
+impl fmt::Display for Operand {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Operand::Immediate(value) => write!(f, "{:+}", value),
+            Operand::Register(register) => write!(f, "{}", (b'a' + register as u8) as char),
+        }
+    }
+}
+
+// How an operation moves the instruction pointer, used without running the program
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flow {
+    Next,
+    Jump,
+    // Either jumps or goes to the next instruction, depending on the registers
+    Branch,
+    Halt,
+}
+
+pub enum Control {
+    Next,
+    Jump(i32),
+    Halt,
+}
+
+#[derive(Clone)]
+pub struct Cpu {
+    pub registers : [i32; REGISTERS],
+    pub input : Vec<i32>,
+    // Next value of the input to be read
+    pub input_position : usize,
+    pub output : Vec<i32>,
+}
+
+impl Cpu {
+    pub fn new() -> Cpu {
+        Cpu {
+            registers : [0; REGISTERS],
+            input : Vec::new(),
+            input_position : 0,
+            output : Vec::new(),
+        }
+    }
+}
+
+pub struct Operation {
+    pub name : &'static str,
+    // First version of the instruction set providing the operation
+    pub version : u32,
+    pub operands : &'static [OperandKind],
+    pub flow : Flow,
+    pub execute : fn(&mut Cpu, &[Operand]) -> Result<Control, &'static str>,
+}
+
+// New operations only need an entry here
+pub static OPERATIONS : [Operation; 11] = [
+    Operation { name : "nop", version : 1, operands : &[OperandKind::Offset], flow : Flow::Next, execute : run_nop },
+    Operation { name : "acc", version : 1, operands : &[OperandKind::Immediate], flow : Flow::Next, execute : run_acc },
+    Operation { name : "jmp", version : 1, operands : &[OperandKind::Offset], flow : Flow::Jump, execute : run_jmp },
+    Operation { name : "mov", version : 2, operands : &[OperandKind::Register, OperandKind::Source], flow : Flow::Next, execute : run_mov },
+    Operation { name : "add", version : 2, operands : &[OperandKind::Register, OperandKind::Source], flow : Flow::Next, execute : run_add },
+    Operation { name : "mul", version : 2, operands : &[OperandKind::Register, OperandKind::Source], flow : Flow::Next, execute : run_mul },
+    Operation { name : "jz", version : 2, operands : &[OperandKind::Source, OperandKind::Offset], flow : Flow::Branch, execute : run_jz },
+    Operation { name : "jnz", version : 2, operands : &[OperandKind::Source, OperandKind::Offset], flow : Flow::Branch, execute : run_jnz },
+    Operation { name : "out", version : 2, operands : &[OperandKind::Source], flow : Flow::Next, execute : run_out },
+    Operation { name : "in", version : 2, operands : &[OperandKind::Register], flow : Flow::Next, execute : run_in },
+    Operation { name : "hlt", version : 2, operands : &[], flow : Flow::Halt, execute : run_hlt },
+];
+
+fn run_nop(_cpu : &mut Cpu, _operands : &[Operand]) -> Result<Control, &'static str> {
+    Ok(Control::Next)
+}
+
+fn run_acc(cpu : &mut Cpu, operands : &[Operand]) -> Result<Control, &'static str> {
+    let value = operands[0].value(cpu);
+    cpu.registers[ACCUMULATOR] = cpu.registers[ACCUMULATOR].checked_add(value).ok_or("Arithmetic overflow")?;
+    Ok(Control::Next)
+}
+
+fn run_jmp(cpu : &mut Cpu, operands : &[Operand]) -> Result<Control, &'static str> {
+    Ok(Control::Jump(operands[0].value(cpu)))
+}
+
+fn run_mov(cpu : &mut Cpu, operands : &[Operand]) -> Result<Control, &'static str> {
+    cpu.registers[operands[0].register()] = operands[1].value(cpu);
+    Ok(Control::Next)
+}
+
+fn run_add(cpu : &mut Cpu, operands : &[Operand]) -> Result<Control, &'static str> {
+    let register = operands[0].register();
+    cpu.registers[register] = cpu.registers[register].checked_add(operands[1].value(cpu)).ok_or("Arithmetic overflow")?;
+    Ok(Control::Next)
+}
+
+fn run_mul(cpu : &mut Cpu, operands : &[Operand]) -> Result<Control, &'static str> {
+    let register = operands[0].register();
+    cpu.registers[register] = cpu.registers[register].checked_mul(operands[1].value(cpu)).ok_or("Arithmetic overflow")?;
+    Ok(Control::Next)
+}
+
+fn run_jz(cpu : &mut Cpu, operands : &[Operand]) -> Result<Control, &'static str> {
+    if operands[0].value(cpu) == 0 { Ok(Control::Jump(operands[1].value(cpu))) } else { Ok(Control::Next) }
+}
+
+fn run_jnz(cpu : &mut Cpu, operands : &[Operand]) -> Result<Control, &'static str> {
+    if operands[0].value(cpu) != 0 { Ok(Control::Jump(operands[1].value(cpu))) } else { Ok(Control::Next) }
+}
+
+fn run_out(cpu : &mut Cpu, operands : &[Operand]) -> Result<Control, &'static str> {
+    let value = operands[0].value(cpu);
+    cpu.output.push(value);
+    Ok(Control::Next)
+}
+
+fn run_in(cpu : &mut Cpu, operands : &[Operand]) -> Result<Control, &'static str> {
+    let value = *cpu.input.get(cpu.input_position).ok_or("Input buffer is empty")?;
+    cpu.input_position += 1;
+    cpu.registers[operands[0].register()] = value;
+    Ok(Control::Next)
+}
+
+fn run_hlt(_cpu : &mut Cpu, _operands : &[Operand]) -> Result<Control, &'static str> {
+    Ok(Control::Halt)
+}
+
+pub fn find_operation(name : &str, isa : u32) -> Result<&'static Operation, &'static str> {
+    match OPERATIONS.iter().find(|operation| operation.name == name) {
+        Some(operation) if operation.version <= isa => Ok(operation),
+        Some(_) => Err("Operation not available in this ISA version"),
+        None => Err("No such operation found!"),
+    }
+}
+
+pub fn parse_operands(operation : &Operation, arguments : &[&str]) -> Result<Vec<Operand>, &'static str> {
+    if arguments.len() != operation.operands.len() { return Err("Wrong number of arguments"); }
+
+    operation.operands.iter().zip(arguments.iter())
+        .map(|(&kind, argument)| Operand::parse(argument, kind))
+        .collect()
+}
+
+// The operation a corrupted 'jmp' or 'nop' was meant to be, both take a single offset
+pub fn corrupted_counterpart(operation : &Operation) -> Option<&'static Operation> {
+    let name = match operation.name {
+        "jmp" => "nop",
+        "nop" => "jmp",
+        _ => return None,
+    };
+
+    OPERATIONS.iter().find(|other| other.name == name)
+}
+
+// Version declared by an '.isa <n>' line at the start of a program
+pub fn parse_directive(line : &str) -> Option<Result<u32, String>> {
+    let version = line.trim().strip_prefix(".isa")?.trim();
+
+    Some(match version.parse() {
+        Ok(version) if (1..=LATEST_ISA).contains(&version) => Ok(version),
+        _ => Err(format!("Unknown ISA version '{}' (expected 1 to {})", version, LATEST_ISA)),
+    })
+}
+
+// Pointers an operation can move to from `pointer`, empty when it halts
+pub fn successors(operation : &Operation, operands : &[Operand], pointer : i64) -> Vec<i64> {
+    let offset : i64 = operation.operands.iter()
+        .position(|&kind| kind == OperandKind::Offset)
+        .map(|position| match operands[position] {
+            Operand::Immediate(value) => value as i64,
+            Operand::Register(_) => unreachable!("Offsets are always immediate values"),
+        })
+        .unwrap_or(1);
+
+    match operation.flow {
+        Flow::Next => vec![pointer + 1],
+        Flow::Jump => vec![pointer + offset],
+        Flow::Branch => vec![pointer + 1, pointer + offset],
+        Flow::Halt => Vec::new(),
+    }
+}
+
+// Change an operation makes to the accumulator, None when it depends on the registers or the input
+pub fn accumulator_delta(operation : &Operation, operands : &[Operand]) -> Option<i32> {
+    match (operation.name, operands) {
+        ("acc", [Operand::Immediate(value)]) => Some(*value),
+        ("add", [Operand::Register(ACCUMULATOR), Operand::Immediate(value)]) => Some(*value),
+        // A register as first operand is the one written by the operation
+        (_, [Operand::Register(ACCUMULATOR), ..]) if operation.operands[0] == OperandKind::Register => None,
+        _ => Some(0),
+    }
+}
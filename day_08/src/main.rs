@@ -1,27 +1,33 @@
-use std::fs::File;
+use std::convert::TryFrom;
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
 use std::io::{BufRead, BufReader};
+use std::process;
+
+mod asm;
+mod cfg;
+mod debug;
+mod isa;
+mod repair;
+mod trace;
+
+use isa::{Control, Cpu, Flow, Operand, Operation, ACCUMULATOR, REGISTERS};
 
 // TODO:
 // Errors should not be constructed directly from a str
 
-#[derive(Debug, Clone, Copy)]
-enum OperationCode {
-    NOP,
-    ACC,
-    JMP
-}
-
 struct Instruction {
-    operation : OperationCode,
-    argument : Option<i32>,
+    operation : &'static Operation,
+    operands : Vec<Operand>,
     executed : bool,
 }
 
 impl Instruction {
-    pub fn new(operation : OperationCode, argument : Option<i32>) -> Instruction {
+    pub fn new(operation : &'static Operation, operands : Vec<Operand>) -> Instruction {
         Instruction {
-            operation : operation,
-            argument : argument,
+            operation,
+            operands,
             executed : false,
         }
     }
@@ -30,136 +36,219 @@ impl Instruction {
         self.executed = value;
     }
 
-    fn get_operation(&mut self) -> OperationCode {
-        return self.operation.clone();
+    fn is_executed(&self) -> bool {
+        self.executed
     }
 
-    fn set_operation(&mut self, value : OperationCode) {
-        self.operation = value;
+    fn get_operation(&self) -> &'static Operation {
+        self.operation
     }
 
-    fn run_instruction(&mut self, mut current_pointer : u32, mut accumulator : i32) -> Result<(u32, i32), &'static str> {
-        match self.operation {
-            OperationCode::NOP => {
-                if self.executed { return Err("Instruction already executed")}
-
-                current_pointer = current_pointer + 1;
-            }
-            OperationCode::JMP => {
-                if self.executed { return Err("Instruction already executed")}
-
-                let argument_value : i32 = match self.argument {
-                    Some(value) => value,
-                    None => return Err("ACC Command not given an argument"),
-                };
-
-                current_pointer = (current_pointer as i32 + argument_value) as u32;
-            }
-            OperationCode::ACC => {
-                if self.executed { return Err("Instruction already executed")}
+    fn get_operands(&self) -> &[Operand] {
+        &self.operands
+    }
 
-                let argument_value : i32 = match self.argument {
-                    Some(value) => value,
-                    None => return Err("ACC Command not given an argument"),
-                };
+    fn set_operation(&mut self, value : &'static Operation) {
+        self.operation = value;
+    }
 
-                accumulator = accumulator + argument_value;
-                current_pointer = current_pointer + 1;
-            },
-        }
+    // Returns the next pointer, or None when the instruction halts the machine
+    fn run_instruction(&mut self, current_pointer : u32, cpu : &mut Cpu) -> Result<Option<u32>, &'static str> {
+        let control : Control = (self.operation.execute)(cpu, &self.operands)?;
+
+        let next_pointer : Option<u32> = match control {
+            Control::Next => Some(current_pointer.checked_add(1).ok_or("Pointer outside of the program")?),
+            Control::Jump(offset) => Some(i32::try_from(current_pointer).ok()
+                .and_then(|pointer| pointer.checked_add(offset))
+                .and_then(|pointer| u32::try_from(pointer).ok())
+                .ok_or("Jump outside of the program")?),
+            Control::Halt => None,
+        };
 
         self.executed = true;
+        Ok(next_pointer)
+    }
+}
 
-        return Ok((current_pointer, accumulator));
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.operation.name)?;
+        for operand in self.operands.iter() {
+            write!(f, " {}", operand)?;
+        }
+        Ok(())
     }
 }
 
+// Everything an instruction can change, so it can be undone
+#[derive(Clone, Copy)]
+struct Snapshot {
+    pointer : u32,
+    registers : [i32; REGISTERS],
+    input_position : usize,
+    output_length : usize,
+    halted : bool,
+}
+
 struct Machine {
     current_pointer : u32,
-    accumulator : i32,
+    cpu : Cpu,
+    halted : bool,
+    isa : u32,
+    // Conditional jumps make a second run of an instruction legitimate
+    has_branches : bool,
     instructions : Vec<Instruction>
 }
 
 impl Machine {
-    pub fn new() -> Machine {
+    pub fn new(isa : u32) -> Machine {
         Machine {
             current_pointer : 0,
-            accumulator : 0,
+            cpu : Cpu::new(),
+            halted : false,
+            isa,
+            has_branches : false,
             instructions : Vec::new()
         }
     }
 
-    fn add_instruction(&mut self, code : &str, argument : i32) -> Result<&'static str, &'static str> {
-        let operation_code : OperationCode = match code {
-            "nop" => OperationCode::NOP,
-            "acc" => OperationCode::ACC,
-            "jmp" => OperationCode::JMP,
-            _ => return Err("No such operation found!")
-        };
+    fn add_instruction(&mut self, code : &str, arguments : &[&str]) -> Result<&'static str, &'static str> {
+        let operation : &'static Operation = isa::find_operation(code, self.isa)?;
+        let operands : Vec<Operand> = isa::parse_operands(operation, arguments)?;
+
+        self.has_branches |= operation.flow == Flow::Branch;
 
-        let new_instruction : Instruction = Instruction::new(operation_code, Some(argument));
+        let new_instruction : Instruction = Instruction::new(operation, operands);
         self.instructions.push(new_instruction);
-        return Ok("Instruction added successfully");
+        Ok("Instruction added successfully")
+    }
+
+    fn set_input(&mut self, input : Vec<i32>) {
+        self.cpu.input = input;
+        self.cpu.input_position = 0;
+    }
+
+    fn get_current_pointer(&self) -> u32 {
+        self.current_pointer
+    }
+
+    fn get_accumulator(&self) -> i32 {
+        self.cpu.registers[ACCUMULATOR]
+    }
+
+    fn get_registers(&self) -> &[i32] {
+        &self.cpu.registers
+    }
+
+    fn get_output(&self) -> &[i32] {
+        &self.cpu.output
+    }
+
+    fn get_isa(&self) -> u32 {
+        self.isa
+    }
+
+    fn get_instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    fn is_finished(&self) -> bool {
+        self.halted || self.current_pointer == self.instructions.len() as u32
+    }
+
+    // Runs the instruction under the pointer, the machine is left untouched on error
+    fn step_machine(&mut self) -> Result<(), &'static str> {
+        let index : usize = self.current_pointer as usize;
+        if index >= self.instructions.len() { return Err("Pointer outside of the program"); }
+        if self.instructions[index].is_executed() && !self.has_branches { return Err("Instruction already executed"); }
+
+        // A failing instruction is undone so it doesn't leave half its changes behind
+        let snapshot : Snapshot = self.snapshot();
+        match self.instructions[index].run_instruction(self.current_pointer, &mut self.cpu) {
+            Ok(Some(pointer)) => self.current_pointer = pointer,
+            Ok(None) => self.halted = true,
+            Err(error) => {
+                self.restore_cpu(&snapshot);
+                return Err(error);
+            },
+        }
+
+        Ok(())
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            pointer : self.current_pointer,
+            registers : self.cpu.registers,
+            input_position : self.cpu.input_position,
+            output_length : self.cpu.output.len(),
+            halted : self.halted,
+        }
+    }
+
+    // Goes back to the state before the instruction at the snapshot pointer was executed
+    fn rewind_machine(&mut self, snapshot : &Snapshot) {
+        self.instructions[snapshot.pointer as usize].set_executed(false);
+        self.current_pointer = snapshot.pointer;
+        self.restore_cpu(snapshot);
+        self.halted = snapshot.halted;
     }
 
-    fn run_machine(&mut self, on_loop_throw_error : bool) -> Result<i32, &'static str> {
+    fn restore_cpu(&mut self, snapshot : &Snapshot) {
+        self.cpu.registers = snapshot.registers;
+        self.cpu.input_position = snapshot.input_position;
+        self.cpu.output.truncate(snapshot.output_length);
+    }
 
-        loop {
-            let index : usize= self.current_pointer as usize;
-            let result : Result<(u32, i32), &'static str> = self.instructions[index]
-                .run_instruction(self.current_pointer, self.accumulator);
+    // Programs with branches may loop without running into an executed instruction, so the steps are bounded
+    fn run_machine(&mut self, on_loop_throw_error : bool, max_steps : usize) -> Result<i32, &'static str> {
 
-            let new_values : (u32, i32) = match result {
-                Ok(value) => value,
+        for _ in 0..max_steps {
+            match self.step_machine() {
+                Ok(()) => (),
                 Err("Instruction already executed") => {
-                    if !on_loop_throw_error { break; }
+                    if !on_loop_throw_error { return Ok(self.get_accumulator()); }
                     else { return Err("Loop found!"); }
                 },
                 Err(e) => return Err(e),
             };
-            
-            self.current_pointer = new_values.0;
-            self.accumulator = new_values.1;
 
-            if self.current_pointer == self.instructions.len() as u32 { break; }
+            if self.is_finished() { return Ok(self.get_accumulator()); }
         }
 
-        return Ok(self.accumulator);
+        if self.is_finished() { Ok(self.get_accumulator()) } else { Err("Step budget exhausted") }
     }
 
-    fn fix_machine(&mut self) -> Result<i32, &'static str> {
-        let number_of_instruction : usize = self.instructions.len();
-        let mut accumulator_fixed : Option<i32> = None;
-
-        for index in 0..number_of_instruction {
-            let prev_code : OperationCode = self.instructions[index].get_operation();
+    // Runs until the program ends, for programs whose loops are expected to finish,
+    // `on_step` sees the pointer and instruction of every step with the accumulator after it
+    fn run_until_halt(&mut self, max_steps : usize, on_step : &mut dyn FnMut(u32, &Instruction, i32)) -> Result<i32, &'static str> {
+        for _ in 0..max_steps {
+            if self.is_finished() { return Ok(self.get_accumulator()); }
 
-            let next_code : OperationCode = match prev_code {
-                OperationCode::ACC => continue,
-                OperationCode::JMP => OperationCode::NOP,
-                OperationCode::NOP => OperationCode::JMP,
-            };
-
-            self.instructions[index].set_operation(next_code);
-            let result : Result<i32, &'static str> = self.run_machine(true);
-
-            match result {
-                Ok(value) => {
-                    accumulator_fixed = Some(value);
-                    break;
-                },
-                Err("Loop found!") => {
-                    self.instructions[index].set_operation(prev_code);
-                    self.reset_machine();
-                },
+            let pointer : u32 = self.current_pointer;
+            match self.step_machine() {
+                Ok(()) => (),
+                Err("Instruction already executed") => return Err("Loop found!"),
                 Err(e) => return Err(e),
             }
+
+            on_step(pointer, &self.instructions[pointer as usize], self.get_accumulator());
         }
 
-        match accumulator_fixed {
-            Some(value) => return Ok(value),
-            None => return Err("This machine can't be fixed"),
+        if self.is_finished() { Ok(self.get_accumulator()) } else { Err("Step budget exhausted") }
+    }
+
+    // Applies the first jmp/nop flip that makes the program end, then runs it
+    fn fix_machine(&mut self, max_steps : usize) -> Result<i32, &'static str> {
+        let repairs : Vec<repair::Repair> = match repair::find_repairs(self, false) {
+            Ok(repairs) => repairs,
+            Err(repair::RepairError::AlreadyTerminates(_)) => return self.run_machine(true, max_steps),
+            Err(_) => return Err("This machine can't be fixed"),
         };
+
+        self.instructions[repairs[0].index].set_operation(repairs[0].replacement);
+        self.reset_machine();
+        self.run_machine(true, max_steps)
     }
 
     fn clean_executed(&mut self) {
@@ -171,49 +260,182 @@ impl Machine {
     fn reset_machine(&mut self) {
         self.clean_executed();
         self.current_pointer = 0;
-        self.accumulator = 0;
+        self.halted = false;
+        self.cpu.registers = [0; REGISTERS];
+        self.cpu.input_position = 0;
+        self.cpu.output.clear();
     }
 }
 
-fn main() {
-    let filename = "src/input.txt";
+const DEFAULT_STEP_BUDGET : usize = 1_000_000;
+// Commands taking the program file as their first argument
+const FILE_COMMANDS : [&str; 6] = ["debug", "run", "repair", "cfg", "disasm", "asm"];
 
-    let file = File::open(filename).unwrap();
+// Programs may start with an '.isa <n>' line, otherwise they use `default_isa`
+fn load_machine(filename : &str, default_isa : u32) -> Result<Machine, String> {
+    let file = File::open(filename).map_err(|error| format!("Could not open '{}': {}", filename, error))?;
     let reader = BufReader::new(file);
 
     let data : Vec<String> = reader.lines()
-        .collect::<Result<_, _>>().unwrap();
+        .collect::<Result<_, _>>().map_err(|error| format!("Could not read '{}': {}", filename, error))?;
 
-    let mut machine_emulator = Machine::new();
+    let first_line : Option<(usize, &String)> = data.iter().enumerate().find(|(_, line)| !line.trim().is_empty());
+    let (isa, directive_line) : (u32, Option<usize>) = match first_line.and_then(|(index_line, line)| isa::parse_directive(line).map(|version| (index_line, version))) {
+        Some((index_line, Ok(version))) => (version, Some(index_line)),
+        Some((index_line, Err(error))) => return Err(format!("Line {}: {}", index_line + 1, error)),
+        None => (default_isa, None),
+    };
+
+    let mut machine_emulator = Machine::new(isa);
+
+    for (index_line, line) in data.iter().enumerate() {
+        if line.trim().is_empty() || Some(index_line) == directive_line { continue; }
 
-    for line in data.iter() {
         let split : Vec<&str> = line.split_whitespace().collect::<Vec<&str>>();
-        
+
         let code : &str = split[0];
-        let argument : &str = split[1];
+        machine_emulator.add_instruction(code, &split[1..])
+            .map_err(|error| format!("Line {}: {}", index_line + 1, error))?;
+    }
 
-        let argument : i32 = match argument.parse() {
-            Ok(value) => value,
-            Err(e) => panic!(e),
-        };
+    Ok(machine_emulator)
+}
 
-        match machine_emulator.add_instruction(code, argument) {
-            Ok(_) => (),
-            Err(e) => panic!(e),
+fn main() {
+    let args : Vec<String> = env::args().skip(1).collect();
+
+    // Commands: 'debug [file]' starts the interactive debugger on a program,
+    // 'run [file]' runs a program until it ends, with '--in' values for its input and
+    // '--trace <output>' recording every step, 'asm <source> [output]' assembles a source with labels,
+    // 'repair [file]' finds the corrupted instruction, every possible one with '--all',
+    // 'cfg [file]' and 'disasm [file]' analyse a program without running it, 'dot <output>' exports its graph
+    let command : Option<&str> = args.first().map(|arg| arg.as_str()).filter(|arg| !arg.starts_with("--"));
+    let filename : &str = match (command, args.get(1), args.iter().position(|arg| arg == "--input")) {
+        (_, _, Some(index)) => args.get(index + 1).expect("Missing file after '--input'"),
+        (Some(name), Some(file), None) if FILE_COMMANDS.contains(&name) && !file.starts_with("--") => file,
+        _ => "src/input.txt",
+    };
+    let default_isa : u32 = match args.iter().position(|arg| arg == "--isa") {
+        Some(index) => args.get(index + 1).and_then(|arg| arg.parse().ok())
+            .filter(|version| (1..=isa::LATEST_ISA).contains(version))
+            .unwrap_or_else(|| panic!("Expected an ISA version from 1 to {} after '--isa'", isa::LATEST_ISA)),
+        None => isa::DEFAULT_ISA,
+    };
+    let input : Vec<i32> = match args.iter().position(|arg| arg == "--in") {
+        Some(index) => args.get(index + 1).expect("Missing values after '--in'")
+            .split(',')
+            .map(|value| value.trim().parse().unwrap_or_else(|_| panic!("Invalid input value '{}'", value)))
+            .collect(),
+        None => Vec::new(),
+    };
+    let max_steps : usize = match args.iter().position(|arg| arg == "--steps") {
+        Some(index) => args.get(index + 1).and_then(|arg| arg.parse().ok()).expect("Expected a number after '--steps'"),
+        None => DEFAULT_STEP_BUDGET,
+    };
+
+    if command == Some("asm") {
+        let source : String = fs::read_to_string(filename).unwrap_or_else(|error| panic!("Could not open '{}': {}", filename, error));
+        let program : String = match asm::assemble(&source, default_isa) {
+            Ok(program) => program,
+            Err(error) => {
+                println!("{}", error);
+                process::exit(1);
+            },
         };
+
+        match args.get(2).filter(|arg| !arg.starts_with("--")) {
+            Some(output) => fs::write(output, program).unwrap_or_else(|error| panic!("Could not write '{}': {}", output, error)),
+            None => print!("{}", program),
+        }
+        return;
+    }
+
+    let mut machine_emulator = load_machine(filename, default_isa).unwrap_or_else(|error| panic!("{}", error));
+    machine_emulator.set_input(input);
+
+    match command {
+        Some("debug") => {
+            debug::Debugger::new(&mut machine_emulator, max_steps).run();
+            return;
+        },
+        Some("run") => {
+            let mut steps : usize = 0;
+            let result = match args.iter().position(|arg| arg == "--trace") {
+                Some(index) => {
+                    let trace_file : &str = args.get(index + 1).expect("Missing file after '--trace'");
+                    let mut trace = trace::Trace::create(trace_file).unwrap_or_else(|error| panic!("{}", error));
+                    let result = machine_emulator.run_until_halt(max_steps, &mut |pointer, instruction, accumulator| {
+                        trace.record(pointer, instruction, accumulator);
+                        steps += 1;
+                    });
+                    trace.finish().unwrap_or_else(|error| panic!("{}", error));
+                    result
+                },
+                None => machine_emulator.run_until_halt(max_steps, &mut |_, _, _| steps += 1),
+            };
+
+            println!("ISA version: {}", machine_emulator.get_isa());
+            println!("Registers: {:?}", machine_emulator.get_registers());
+            println!("Output: {:?}", machine_emulator.get_output());
+            println!("Steps: {}", steps);
+            match result {
+                Ok(accumulator_value) => println!("Program ended, accumulator value: {}", accumulator_value),
+                Err(error) => {
+                    println!("Program stopped at {}: {}", machine_emulator.get_current_pointer(), error);
+                    process::exit(1);
+                },
+            }
+            return;
+        },
+        Some("repair") => {
+            match repair::find_repairs(&machine_emulator, args.iter().any(|arg| arg == "--all")) {
+                Ok(repairs) => {
+                    for repair in repairs.iter() {
+                        let operands : String = machine_emulator.get_instructions()[repair.index].get_operands().iter()
+                            .map(|operand| format!(" {}", operand))
+                            .collect();
+                        println!("Changed instruction {}: {}{} -> {}{}, accumulator value: {}",
+                            repair.index, repair.original.name, operands, repair.replacement.name, operands, repair.accumulator);
+                    }
+                },
+                Err(error) => {
+                    println!("{}", error);
+                    process::exit(1);
+                },
+            }
+            return;
+        },
+        Some("cfg") => {
+            cfg::print_summary(&cfg::analyse(&machine_emulator));
+            return;
+        },
+        Some("disasm") => {
+            print!("{}", cfg::disassemble(&machine_emulator, &cfg::analyse(&machine_emulator)));
+            return;
+        },
+        Some("dot") => {
+            let output : &str = args.get(1).filter(|arg| !arg.starts_with("--")).expect("Missing output file after 'dot'");
+            if let Err(error) = cfg::write_dot(output, &machine_emulator, &cfg::analyse(&machine_emulator)) {
+                println!("{}", error);
+                process::exit(1);
+            }
+            return;
+        },
+        Some(other) => panic!("Unknown command '{}' (expected 'debug', 'run', 'repair', 'cfg', 'disasm' or 'dot')", other),
+        None => (),
     }
 
-    let accumulator_value : i32 = match machine_emulator.run_machine(false) {
+    let accumulator_value : i32 = match machine_emulator.run_machine(false, max_steps) {
         Ok(value) => value,
-        Err(e) => panic!(e)
+        Err(e) => panic!("{}", e)
     };
 
     println!("Machine emulator final accumulator value: {}", accumulator_value);
     machine_emulator.reset_machine();
 
-    let accumulator_value : i32 = match machine_emulator.fix_machine() {
+    let accumulator_value : i32 = match machine_emulator.fix_machine(max_steps) {
         Ok(value) => value,
-        Err(e) => panic!(e)
+        Err(e) => panic!("{}", e)
     };
 
     println!("Machine emulator final accumulator value (with fix): {}", accumulator_value);
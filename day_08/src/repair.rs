@@ -0,0 +1,111 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use crate::isa::{self, Flow, Operation};
+use crate::{Instruction, Machine};
+
+pub struct Repair {
+    pub index : usize,
+    pub original : &'static Operation,
+    pub replacement : &'static Operation,
+    // Accumulator once the repaired program ends
+    pub accumulator : i64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RepairError {
+    // Conditional jumps make the path depend on the registers
+    Branches,
+    // The instruction changes the accumulator by an amount only known when running
+    UnknownDelta(usize),
+    AlreadyTerminates(i64),
+    NoRepair,
+}
+
+impl fmt::Display for RepairError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RepairError::Branches => write!(f, "Programs with conditional jumps can't be repaired statically"),
+            RepairError::UnknownDelta(index) => write!(f, "Instruction {} changes the accumulator by an unknown amount", index),
+            RepairError::AlreadyTerminates(accumulator) => write!(f, "The program already terminates, accumulator: {}", accumulator),
+            RepairError::NoRepair => write!(f, "No single change makes the program terminate"),
+        }
+    }
+}
+
+// Node reached after running an operation at `index`, `length` standing for the end of the program
+fn target(operation : &Operation, instruction : &Instruction, index : usize, length : usize) -> Option<usize> {
+    if operation.flow == Flow::Halt { return Some(length); }
+
+    isa::successors(operation, instruction.get_operands(), index as i64).first()
+        .filter(|&&pointer| pointer >= 0 && pointer <= length as i64)
+        .map(|&pointer| pointer as usize)
+}
+
+// Every single jmp/nop flip making the program end, or only the first one found on the executed path.
+// Works backwards from the end once, then walks the executed path once, so it stays linear.
+pub fn find_repairs(machine : &Machine, all : bool) -> Result<Vec<Repair>, RepairError> {
+    let instructions = machine.get_instructions();
+    let length = instructions.len();
+
+    if instructions.iter().any(|instruction| instruction.get_operation().flow == Flow::Branch) {
+        return Err(RepairError::Branches);
+    }
+
+    let mut deltas : Vec<i64> = Vec::with_capacity(length);
+    for (index, instruction) in instructions.iter().enumerate() {
+        match isa::accumulator_delta(instruction.get_operation(), instruction.get_operands()) {
+            Some(delta) => deltas.push(delta as i64),
+            None => return Err(RepairError::UnknownDelta(index)),
+        }
+    }
+
+    let targets : Vec<Option<usize>> = instructions.iter().enumerate()
+        .map(|(index, instruction)| target(instruction.get_operation(), instruction, index, length))
+        .collect();
+
+    let mut predecessors : Vec<Vec<usize>> = vec![Vec::new(); length + 1];
+    for (index, target) in targets.iter().enumerate() {
+        if let Some(target) = target { predecessors[*target].push(index); }
+    }
+
+    // Accumulator gained from each node to the end, only for the nodes that get there
+    let mut to_end : Vec<Option<i64>> = vec![None; length + 1];
+    to_end[length] = Some(0);
+    let mut queue : VecDeque<usize> = VecDeque::from(vec![length]);
+    while let Some(node) = queue.pop_front() {
+        let gained = to_end[node].unwrap();
+        for &predecessor in predecessors[node].iter() {
+            if to_end[predecessor].is_some() { continue; }
+
+            to_end[predecessor] = Some(deltas[predecessor] + gained);
+            queue.push_back(predecessor);
+        }
+    }
+
+    if let Some(accumulator) = to_end[0] { return Err(RepairError::AlreadyTerminates(accumulator)); }
+
+    // The executed path never gets to the end, so a flip on it can't lead back onto it on the way out
+    let mut repairs : Vec<Repair> = Vec::new();
+    let mut visited : Vec<bool> = vec![false; length];
+    let mut accumulator : i64 = 0;
+    let mut pointer : Option<usize> = Some(0);
+
+    while let Some(index) = pointer.filter(|&index| index < length && !visited[index]) {
+        visited[index] = true;
+
+        let original = instructions[index].get_operation();
+        if let Some(replacement) = isa::corrupted_counterpart(original) {
+            let repaired_target = target(replacement, &instructions[index], index, length);
+            if let Some(gained) = repaired_target.and_then(|node| to_end[node]) {
+                repairs.push(Repair { index, original, replacement, accumulator : accumulator + deltas[index] + gained });
+                if !all { break; }
+            }
+        }
+
+        accumulator += deltas[index];
+        pointer = targets[index];
+    }
+
+    if repairs.is_empty() { Err(RepairError::NoRepair) } else { Ok(repairs) }
+}
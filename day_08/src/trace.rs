@@ -0,0 +1,39 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+use crate::Instruction;
+
+// One line per executed instruction: pointer, opcode, arguments and the accumulator once it ran
+pub struct Trace {
+    filename : String,
+    writer : BufWriter<File>,
+    // Writing stops at the first error, reported by `finish`
+    error : Option<io::Error>,
+}
+
+impl Trace {
+    pub fn create(filename : &str) -> Result<Trace, String> {
+        let file = File::create(filename).map_err(|error| format!("Could not create '{}': {}", filename, error))?;
+        let mut trace = Trace { filename : filename.to_string(), writer : BufWriter::new(file), error : None };
+
+        if let Err(error) = writeln!(trace.writer, "pc,opcode,argument,acc") { trace.error = Some(error); }
+        Ok(trace)
+    }
+
+    pub fn record(&mut self, pointer : u32, instruction : &Instruction, accumulator : i32) {
+        if self.error.is_some() { return; }
+
+        let arguments : Vec<String> = instruction.get_operands().iter().map(|operand| operand.to_string()).collect();
+        if let Err(error) = writeln!(self.writer, "{},{},{},{}", pointer, instruction.get_operation().name, arguments.join(" "), accumulator) {
+            self.error = Some(error);
+        }
+    }
+
+    pub fn finish(mut self) -> Result<(), String> {
+        let result = match self.error.take() {
+            Some(error) => Err(error),
+            None => self.writer.flush(),
+        };
+        result.map_err(|error| format!("Could not write '{}': {}", self.filename, error))
+    }
+}
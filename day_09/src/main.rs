@@ -1,69 +1,71 @@
+use std::env;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{self, BufRead, BufReader};
+
+mod stream;
+mod window;
+
+use window::SlidingWindow;
+
+const DEFAULT_PREAMBLE : usize = 25;
+
+// Two pointers over the contiguous range, numbers are never negative so growing the range
+// can only increase its sum and shrinking it can only decrease it
+pub fn find_range_that_sums(numbers : &[u64], value : u64) -> Option<(usize, usize)> {
+    let mut start : usize = 0;
+    let mut sum : u64 = 0;
+
+    for (end, &number) in numbers.iter().enumerate() {
+        // Numbers are dropped from the start until the new one fits, a sum that overflows is above any value
+        while start < end && sum.checked_add(number).map(|total| total > value).unwrap_or(true) {
+            sum -= numbers[start];
+            start += 1;
+        }
+        sum += number;
+
+        // A range needs at least two numbers
+        if sum == value && end > start { return Some((start, end)); }
+    }
+
+    None
+}
 
 struct List {
-    preamble : usize,
     numbers : Vec<u64>,
+    window : SlidingWindow,
     invalid_found : Option<u64>,
 }
 
 impl List {
     pub fn new(preamble : usize) -> List {
         List {
-            preamble : preamble,
             numbers : Vec::new(),
+            window : SlidingWindow::new(preamble),
             invalid_found : None,
         }
     }
 
     fn get_invalid_number(&self) -> Option<u64> {
-        return self.invalid_found.clone();
+        self.invalid_found
     }
 
     fn add(&mut self, value : u64) {
-        let invalid_found_already : bool = match self.invalid_found {
-            Some(_) => true,
-            None => false,
-        };
+        let invalid_found_already : bool = self.invalid_found.is_some();
 
-        if !invalid_found_already && self.numbers.len() >= self.preamble {
-            if !self.check(value) { self.invalid_found = Some(value); }
-        }
+        if !invalid_found_already && self.window.is_full()
+            && !self.check(value) { self.invalid_found = Some(value); }
 
         self.numbers.push(value);
+        self.window.push(value);
     }
 
+    // The value is the sum of two of the previous `preamble` numbers
     fn check(&self, value : u64) -> bool {
-        let number_of_elements : usize = self.numbers.len();
-        let first_to_consider : usize = number_of_elements - self.preamble;
-
-        for index_i in first_to_consider..number_of_elements {
-            for index_j in index_i..number_of_elements {
-                if self.numbers[index_i] + self.numbers[index_j] == value {
-                    return true;
-                }
-            }
-        }
-
-        return false;
+        self.window.is_valid(value)
     }
 
     fn find_indexes_that_sum(&self, value : u64) -> Result<(usize, usize), &'static str> {
-        let number_of_elements : usize = self.numbers.len();
-
-        for index_i in 0..number_of_elements {
-            for index_j in (index_i + 1)..number_of_elements {
-
-                let mut sum : u64 = 0;
-                for index in index_i..(index_j + 1) {
-                    sum = sum + self.numbers[index];
-                }
-
-                if sum == value { return Ok((index_i, index_j)); }
-            }
-        }
-
-        return Err("No such number found!");
+        find_range_that_sums(&self.numbers, value).ok_or("No such number found!")
     }
 
     fn sum_max_min(&self, start : usize, end : usize) -> u64 {
@@ -76,7 +78,7 @@ impl List {
             if value > max { max = value; }
         }
         
-        return min + max;
+        min + max
     }
 
     fn find_sum_between_sum_of_invalid(&self) -> Result<u64, &'static str> {
@@ -85,16 +87,33 @@ impl List {
             None => return Err("There is no invalid number in list!"),
         };
 
-        let (start, end) : (usize, usize) = match self.find_indexes_that_sum(value) {
-            Ok(value) => value,
-            Err(e) => return Err(e),
-        };
+        let (start, end) : (usize, usize) = self.find_indexes_that_sum(value)?;
 
-        return Ok(self.sum_max_min(start, end));
+        Ok(self.sum_max_min(start, end))
     }
 }
 
 fn main() {
+    let args : Vec<String> = env::args().skip(1).collect();
+
+    let preamble : usize = match args.iter().position(|arg| arg == "--preamble") {
+        // A number is valid as the sum of two earlier ones, so fewer than two can never validate anything
+        Some(index) => args.get(index + 1).and_then(|arg| arg.parse().ok()).filter(|&preamble| preamble >= 2)
+            .expect("Expected a number of at least 2 after '--preamble'"),
+        None => DEFAULT_PREAMBLE,
+    };
+
+    // 'stream' validates numbers from the standard input as they come,
+    // '--history <n>' also looks for each weakness within the last n numbers
+    if args.first().map(|arg| arg.as_str()) == Some("stream") {
+        let history : Option<usize> = args.iter().position(|arg| arg == "--history")
+            .map(|index| args.get(index + 1).and_then(|arg| arg.parse().ok()).expect("Expected a number after '--history'"));
+
+        let stdin = io::stdin();
+        stream::run(stdin.lock(), preamble, history);
+        return;
+    }
+
     let filename = "src/input.txt";
 
     let file = File::open(filename).unwrap();
@@ -103,7 +122,7 @@ fn main() {
     let data : Vec<String> = reader.lines()
         .collect::<Result<_, _>>().unwrap();
 
-    let mut list : List = List::new(25);
+    let mut list : List = List::new(preamble);
 
     for line in data.iter() {
         let value : u64 = match line.parse() {
@@ -121,6 +140,6 @@ fn main() {
     
     match list.find_sum_between_sum_of_invalid() {
         Ok(value) => println!("The weakness: {}", value),
-        Err(e) => panic!(e),
+        Err(e) => panic!("{}", e),
     }
 }
@@ -0,0 +1,69 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+
+use crate::find_range_that_sums;
+use crate::window::SlidingWindow;
+
+// Validates numbers as they arrive, one per line, only keeping the preamble window
+// and, when `history` is given, that many previous numbers to look for each weakness in
+pub fn run(input : impl BufRead, preamble : usize, history : Option<usize>) {
+    let stdout = io::stdout();
+
+    // Failing to write means the reader went away (e.g. '| head'), so there is nobody left to report to
+    let _ = validate(input, preamble, history, &mut stdout.lock());
+}
+
+fn validate(input : impl BufRead, preamble : usize, history : Option<usize>, output : &mut impl Write) -> io::Result<()> {
+    let mut window : SlidingWindow = SlidingWindow::new(preamble);
+    let mut recent : VecDeque<u64> = VecDeque::with_capacity(history.unwrap_or(0) + 1);
+    let mut numbers_read : u64 = 0;
+    let mut invalid_found : u64 = 0;
+
+    for (index_line, line) in input.lines().enumerate() {
+        let line = match line {
+            Ok(line) => line,
+            Err(error) => {
+                eprintln!("Could not read the input: {}", error);
+                break;
+            },
+        };
+        if line.trim().is_empty() { continue; }
+
+        let value : u64 = match line.trim().parse() {
+            Ok(value) => value,
+            Err(error) => {
+                eprintln!("Line {}: invalid number '{}': {}", index_line + 1, line.trim(), error);
+                continue;
+            },
+        };
+        numbers_read += 1;
+
+        if window.is_full() && !window.is_valid(value) {
+            invalid_found += 1;
+            writeln!(output, "Invalid number {} (number {})", value, numbers_read)?;
+
+            if history.is_some() {
+                let numbers : &[u64] = recent.make_contiguous();
+                match find_range_that_sums(numbers, value) {
+                    Some((start, end)) => {
+                        let range = &numbers[start..=end];
+                        let weakness = range.iter().min().unwrap() + range.iter().max().unwrap();
+                        writeln!(output, "\tweakness: {} (numbers {} to {})", weakness,
+                            numbers_read - (numbers.len() - start) as u64, numbers_read - (numbers.len() - end) as u64)?;
+                    },
+                    None => writeln!(output, "\tno weakness within the last {} numbers", numbers.len())?,
+                }
+            }
+            // Reports must show up while the feed is still running
+            output.flush()?;
+        }
+
+        window.push(value);
+        if let Some(history) = history {
+            if recent.len() == history { recent.pop_front(); }
+            if history > 0 { recent.push_back(value); }
+        }
+    }
+
+    writeln!(output, "Numbers read: {}, invalid numbers: {}", numbers_read, invalid_found)
+}
@@ -0,0 +1,56 @@
+use std::collections::{HashMap, VecDeque};
+
+// The last `preamble` values with the sums of every pair of them, so checking a value is a single lookup.
+// Sliding by one value only updates the pairs of the value leaving and of the value entering,
+// so a push costs O(preamble) and a whole list O(n * preamble) instead of O(n * preamble²).
+pub struct SlidingWindow {
+    preamble : usize,
+    values : VecDeque<u64>,
+    // Number of pairs of two different numbers adding up to each sum
+    pair_sums : HashMap<u64, usize>,
+}
+
+impl SlidingWindow {
+    pub fn new(preamble : usize) -> SlidingWindow {
+        SlidingWindow {
+            preamble,
+            values : VecDeque::with_capacity(preamble + 1),
+            pair_sums : HashMap::new(),
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.values.len() >= self.preamble
+    }
+
+    pub fn is_valid(&self, value : u64) -> bool {
+        self.pair_sums.get(&value).copied().unwrap_or(0) > 0
+    }
+
+    // Pairs of equal numbers don't count, and a sum above u64::MAX can't match any value
+    fn pair_sum(first : u64, second : u64) -> Option<u64> {
+        if first == second { None } else { first.checked_add(second) }
+    }
+
+    pub fn push(&mut self, value : u64) {
+        if self.preamble == 0 { return; }
+
+        if self.values.len() == self.preamble {
+            let oldest = self.values.pop_front().unwrap();
+            for &other in self.values.iter() {
+                let sum = match SlidingWindow::pair_sum(oldest, other) {
+                    Some(sum) => sum,
+                    None => continue,
+                };
+                let count = self.pair_sums.get_mut(&sum).unwrap();
+                *count -= 1;
+                if *count == 0 { self.pair_sums.remove(&sum); }
+            }
+        }
+
+        for &other in self.values.iter() {
+            if let Some(sum) = SlidingWindow::pair_sum(other, value) { *self.pair_sums.entry(sum).or_insert(0) += 1; }
+        }
+        self.values.push_back(value);
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{Adapter, Jolts, Scenario};
+
+// Depth first walk over the ordered adapters, one chain at a time without keeping the others
+pub struct Chains<'a> {
+    scenario : &'a Scenario,
+    // Largest gap any adapter accepts, no adapter further away than this can come next
+    max_tolerance : Jolts,
+    // Outlet the current chains start from
+    outlet_index : usize,
+    // Indexes of the adapters in the current chain
+    path : Vec<usize>,
+    // Next adapter to try after each prefix of the path, one more entry than the path
+    cursors : Vec<usize>,
+}
+
+impl<'a> Chains<'a> {
+    pub fn new(scenario : &'a Scenario) -> Chains<'a> {
+        Chains {
+            scenario,
+            max_tolerance : scenario.adapters.iter().map(|adapter| adapter.get_negative_tolerance()).max().unwrap_or(0),
+            outlet_index : 0,
+            path : Vec::new(),
+            cursors : if scenario.outlets.is_empty() { Vec::new() } else { vec![0] },
+        }
+    }
+}
+
+impl Iterator for Chains<'_> {
+    // The outlet and the adapters of the chain
+    type Item = (Jolts, Vec<Jolts>);
+
+    fn next(&mut self) -> Option<(Jolts, Vec<Jolts>)> {
+        let adapters : &[Adapter] = &self.scenario.adapters;
+
+        loop {
+            // Every chain from this outlet was found, start over from the next one
+            if self.cursors.is_empty() {
+                self.outlet_index += 1;
+                if self.outlet_index >= self.scenario.outlets.len() { return None; }
+                self.cursors.push(0);
+            }
+
+            let outlet_jolts : Jolts = self.scenario.outlets[self.outlet_index];
+            let cursor : usize = *self.cursors.last().unwrap();
+            let current_jolts : Jolts = match self.path.last() {
+                Some(&index) => adapters[index].get_joltage(),
+                None => outlet_jolts,
+            };
+
+            let candidate : Option<&Adapter> = adapters.get(cursor)
+                .filter(|adapter| adapter.get_joltage().checked_sub(current_jolts).is_some_and(|difference| difference <= self.max_tolerance));
+
+            match candidate {
+                Some(adapter) => {
+                    *self.cursors.last_mut().unwrap() += 1;
+                    if !adapter.accepts_joltage(current_jolts) { continue; }
+
+                    self.path.push(cursor);
+                    self.cursors.push(cursor + 1);
+                    if self.scenario.device_accepts_joltage(adapter.get_joltage()) {
+                        return Some((outlet_jolts, self.path.iter().map(|&index| adapters[index].get_joltage()).collect()));
+                    }
+                },
+                None => {
+                    // Nothing else can follow this chain, go back to the previous adapter
+                    self.cursors.pop();
+                    self.path.pop();
+                },
+            }
+        }
+    }
+}
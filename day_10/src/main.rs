@@ -2,14 +2,25 @@ use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::cmp::Ordering;
 use std::fmt;
-use std::collections::VecDeque;
+use std::env;
+
+use num_bigint::BigUint;
+
+mod chains;
+mod stats;
+
+use chains::Chains;
 
 // TODO:
 // Refactoring and cleanup of code
-// Computing orderings could be done in a resonable time if following the same strategy used for counting
 
 type Jolts = i32;
 
+const DEFAULT_CHAIN_LIMIT : usize = 10;
+const DEFAULT_TOLERANCE : Jolts = 3;
+const DEFAULT_DEVICE_OFFSET : Jolts = 3;
+const DEFAULT_OUTLET : Jolts = 0;
+
 // ----------------- Adapter ------------------
 #[derive(Eq, Clone, Debug)]
 struct Adapter {
@@ -20,15 +31,16 @@ struct Adapter {
 impl Adapter {
     pub fn new(jolts : Jolts, negative_tolerance : Jolts) -> Adapter {
         Adapter {
-            jolts : jolts,
-            negative_tolerance : negative_tolerance
+            jolts,
+            negative_tolerance
         }
     }
 
-    fn get_joltage(&self) -> Jolts { return self.jolts; }
+    fn get_joltage(&self) -> Jolts { self.jolts }
+    fn get_negative_tolerance(&self) -> Jolts { self.negative_tolerance }
 
     fn accepts_joltage(&self, joltage : Jolts) -> bool {
-        return self.jolts > joltage && self.jolts - self.negative_tolerance <= joltage;
+        self.jolts > joltage && self.jolts - self.negative_tolerance <= joltage
     }
 }
 
@@ -52,66 +64,48 @@ struct Differential {
 impl Differential {
     pub fn new(differential : Jolts, count : u32) -> Differential {
         Differential {
-            differential : differential,
-            count : count,
+            differential,
+            count,
         }
     }
 }
 
 impl fmt::Display for Differential {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        return write!(f, "Differential of '{}' appears {} time(s)", self.differential, self.count);
-    }
-}
-
-// ----------------- Possibilities ------------------
-#[derive(Debug)]
-struct Possibility {
-    adapter : Adapter,
-    count : u64,
-}
-
-impl Possibility {
-    pub fn new(adapter : Adapter, count : u64) -> Possibility {
-        Possibility {
-            adapter : adapter,
-            count : count,
-        }
+        write!(f, "Differential of '{}' appears {} time(s)", self.differential, self.count)
     }
-
-    fn get_adapter(&self) -> Adapter { return self.adapter.clone(); }
-    fn get_count(&self) -> u64 { return self.count; }
 }
 
 // ----------------- Scenario ------------------
 struct Scenario {
     device_jolts : Jolts,
+    // Device joltage above the highest adapter
+    device_offset : Jolts,
     device_negative_tolerance : Jolts,
-    outlet_jolts : Jolts,
+    // Ordered, a chain can start from any of them
+    outlets : Vec<Jolts>,
     adapters : Vec<Adapter>,
-    orderings : Vec<Vec<Adapter>>,
-    possibilities : Vec<Possibility>
 }
 
 impl Scenario {
-    pub fn new(outlet_jolts : Jolts) -> Scenario {
+    pub fn new(mut outlets : Vec<Jolts>, device_offset : Jolts, device_negative_tolerance : Jolts) -> Scenario {
+        outlets.sort_unstable();
+        outlets.dedup();
+
         Scenario {
             device_jolts : 0,
-            device_negative_tolerance : 3,
-            outlet_jolts : outlet_jolts,
+            device_offset,
+            device_negative_tolerance,
+            outlets,
             adapters : Vec::new(),
-            orderings : Vec::new(),
-            possibilities : Vec::new(),
         }
     }
 
-    fn add_adapter(&mut self, jolts : Jolts) {
-        let negative_tolerance : Jolts = 3;
+    fn add_adapter(&mut self, jolts : Jolts, negative_tolerance : Jolts) {
         self.adapters.push(Adapter::new(jolts, negative_tolerance));
     }
 
-    fn compute_device_output(&mut self) {
-        let device_joltage_addition : Jolts = 3;
+    fn compute_device_output(&mut self) -> Result<(), &'static str> {
         let mut max : Jolts = 0;
 
         for adapter in self.adapters.iter() {
@@ -119,7 +113,22 @@ impl Scenario {
             if adapter_joltage > max { max = adapter_joltage};
         }
 
-        self.device_jolts = max + device_joltage_addition;
+        self.device_jolts = max.checked_add(self.device_offset).ok_or("The device joltage is too big")?;
+        Ok(())
+    }
+
+    // Number of outlets an adapter can be plugged into
+    fn outlets_accepted(&self, adapter : &Adapter) -> usize {
+        self.outlets.iter().filter(|&&outlet| adapter.accepts_joltage(outlet)).count()
+    }
+
+    // Adapters that can be plugged right before the one at `index`, adapters must be ordered first
+    fn feeding_adapters(&self, index : usize) -> impl Iterator<Item = usize> + '_ {
+        let adapter : &Adapter = &self.adapters[index];
+        (0..index).rev()
+            .take_while(move |&previous| adapter.get_joltage().checked_sub(self.adapters[previous].get_joltage())
+                .is_some_and(|difference| difference <= adapter.get_negative_tolerance()))
+            .filter(move |&previous| adapter.accepts_joltage(self.adapters[previous].get_joltage()))
     }
 
     fn order_adapters(&mut self) { self.adapters.sort(); }
@@ -131,7 +140,11 @@ impl Scenario {
         let error_difference_to_big : &'static str = "There is no possible solution, the gap between two adapters is to big";
         if number_adapters == 0 { return Err("There are no adapters in this scenario"); }
 
-        let mut current_jolts : Jolts = self.outlet_jolts;
+        // Starts from the closest outlet the first adapter accepts
+        let mut current_jolts : Jolts = match self.outlets.iter().rev().find(|&&outlet| self.adapters[0].accepts_joltage(outlet)) {
+            Some(&outlet) => outlet,
+            None => return Err(error_difference_to_big),
+        };
         for adapter in self.adapters.iter() {
             if !adapter.accepts_joltage(current_jolts) { return Err(error_difference_to_big); }
 
@@ -140,7 +153,7 @@ impl Scenario {
             current_jolts = new_jolts;
 
             match diffs.iter_mut().find(|diff| diff.differential == difference) {
-                Some(diff) => diff.count = diff.count + 1,
+                Some(diff) => diff.count += 1,
                 None => diffs.push(Differential::new(difference, 1)),
             };
         }
@@ -148,117 +161,88 @@ impl Scenario {
         let difference : Jolts = self.device_jolts - current_jolts;
         if difference > self.device_negative_tolerance { return Err(error_difference_to_big); }
         match diffs.iter_mut().find(|diff| diff.differential == difference) {
-            Some(diff) => diff.count = diff.count + 1,
+            Some(diff) => diff.count += 1,
             None => diffs.push(Differential::new(difference, 1)),
         };
 
-        return Ok(diffs);
+        Ok(diffs)
     }
 
-    // Literally compute all possibilities (too exaustive)
-    fn compute_orderings(&mut self) -> Vec<Vec<Adapter>> {
-        let possibilities : Vec<VecDeque<Adapter>> = self.compute_possibilities_from_point(self.outlet_jolts);
-
-        for possibility in possibilities {
-            match possibility.back() {
-                Some(last_adapter) => {
-                    let difference : Jolts = self.device_jolts - last_adapter.get_joltage();
-                    if difference <= self.device_negative_tolerance && difference > 0 {
-                        self.orderings.push(Vec::from(possibility));
-                    }
-                }
-                None => ()
-            }
-        }
-
-        return self.orderings.clone();
+    fn device_accepts_joltage(&self, joltage : Jolts) -> bool {
+        let difference : Jolts = self.device_jolts - joltage;
+        difference <= self.device_negative_tolerance && difference > 0
     }
-    fn compute_possibilities_from_point(&self, value : Jolts) -> Vec<VecDeque<Adapter>> {
-        //println!("Dealing with value: {}", value);
-        let mut possibilities : Vec<Adapter> = Vec::new();
-        for adapter in self.adapters.iter() {
-            if adapter.accepts_joltage(value) { possibilities.push(adapter.clone()); }
-        }
 
-        let mut available_vectors : Vec<VecDeque<Adapter>> = Vec::new();
-        for possibility in possibilities.iter() {
+    // Lazily goes through every valid chain, adapters must be ordered first
+    fn orderings(&self) -> Chains<'_> {
+        Chains::new(self)
+    }
 
-            let mut future_possibilities : Vec<VecDeque<Adapter>> = self.compute_possibilities_from_point(possibility.get_joltage());
-            for future_possibility in future_possibilities.iter_mut() {
-                future_possibility.push_front(possibility.clone());
-            }
+    // Number of chains reaching each adapter from the outlets, adding up the adapters just before it.
+    // Adapters must be ordered first, so only the few previous ones can feed an adapter.
+    fn count_chains_to_adapters(&self) -> Vec<BigUint> {
+        let mut counts : Vec<BigUint> = Vec::with_capacity(self.adapters.len());
 
-            if future_possibilities.len() == 0 {
-                let mut temp_vecdeque : VecDeque<Adapter> = VecDeque::new();
-                temp_vecdeque.push_back(possibility.clone());
-                future_possibilities.push(temp_vecdeque);
+        for (index, adapter) in self.adapters.iter().enumerate() {
+            let mut count : BigUint = BigUint::from(self.outlets_accepted(adapter));
+            for previous in self.feeding_adapters(index) {
+                count += &counts[previous];
             }
 
-            available_vectors.append(&mut future_possibilities);
+            counts.push(count);
         }
 
-        return available_vectors;
+        counts
     }
 
-    // Compute only the number of orderings
-    fn compute_number_orderings(&mut self) -> u64 {
-        let possibilities : Vec<Possibility> = Vec::new();
-        let adapter : Adapter = match self.adapters.first() {
-            Some(adapter) => adapter.clone(),
-            None => return 0,
-        };
+    fn compute_number_orderings(&self) -> BigUint {
+        let counts : Vec<BigUint> = self.count_chains_to_adapters();
 
-        let (possibilities, _) : (Vec<Possibility>, _) = self.compute_number_possibilities_with_adapter(possibilities, adapter);
-        self.possibilities = possibilities;
+        self.adapters.iter().zip(counts.iter())
+            .filter(|(adapter, _)| self.device_accepts_joltage(adapter.get_joltage()))
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
 
-        let mut count : u64 = 0;
-        for possibility in self.possibilities.iter() {
-            if possibility.get_adapter().accepts_joltage(self.outlet_jolts) {
-                count = count + possibility.get_count();
-            }
-        }
+// Joltages given in the input or on the command line, none can be negative
+fn parse_joltage(text : &str) -> Option<Jolts> {
+    text.parse::<Jolts>().ok().filter(|&value| value >= 0)
+}
 
-        return count;
-    }
-    
-    fn compute_number_possibilities_with_adapter(&self, mut possibilities : Vec<Possibility>, adapter : Adapter) -> (Vec<Possibility>, u64) {
-        let current_jolts : Jolts = adapter.get_joltage();
+// Tolerances given in the input or on the command line, an adapter must accept at least 1 jolt below it
+fn parse_tolerance(text : &str) -> Option<Jolts> {
+    text.parse::<Jolts>().ok().filter(|&value| value > 0)
+}
 
-        // Calculate new possibility
-        let mut valid_adapters : Vec<Adapter> = Vec::new();
-        for adapter in self.adapters.iter() {
-            if adapter.accepts_joltage(current_jolts) { valid_adapters.push(adapter.clone()); }
-        }
+// An adapter joltage, optionally followed by its own tolerance as in '12 tol=2'
+fn parse_adapter(line : &str) -> Result<(Jolts, Option<Jolts>), String> {
+    let mut words = line.split_whitespace();
 
-        let difference_to_device : Jolts = self.device_jolts - current_jolts;
-        let mut count : u64 = 0;
-        if difference_to_device <= self.device_negative_tolerance && difference_to_device > 0 {count = count + 1; }
-        for possible_adapter in valid_adapters.iter() {
-            let copy_adapter : Adapter = possible_adapter.clone();
-            let already_computed : bool = possibilities.iter().any(|possibility| possibility.get_adapter() == copy_adapter);
-            let sub_count : u64;
-
-            if already_computed {
-                sub_count = match possibilities.iter().find(|possibility| possibility.get_adapter() == copy_adapter) {
-                    Some(possibility) => possibility.get_count(),
-                    None => panic!("Should never happen!"),
-                };
-            } else {
-                let result : (Vec<Possibility>, u64) = self.compute_number_possibilities_with_adapter(possibilities, possible_adapter.clone());
-                possibilities = result.0;
-                sub_count = result.1;
-            }
+    let jolts : Jolts = match words.next().and_then(parse_joltage) {
+        Some(value) => value,
+        None => return Err(format!("Invalid adapter '{}'", line.trim())),
+    };
 
-            count = count + sub_count;
-        }
+    let negative_tolerance : Option<Jolts> = match words.next() {
+        Some(word) => match word.strip_prefix("tol=").and_then(parse_tolerance) {
+            Some(value) => Some(value),
+            None => return Err(format!("Invalid tolerance '{}' (expected 'tol=<positive number>')", word)),
+        },
+        None => None,
+    };
 
-        possibilities.push(Possibility::new(adapter, count));
-        return (possibilities, count);
-    }
+    if let Some(word) = words.next() { return Err(format!("Unexpected '{}' after the adapter", word)); }
+
+    Ok((jolts, negative_tolerance))
 }
 
 fn main() {
-    let filename = "src/input.txt";
+    let args : Vec<String> = env::args().skip(1).collect();
+    let filename : &str = match args.iter().position(|arg| arg == "--input") {
+        Some(index) => args.get(index + 1).expect("Missing file after '--input'"),
+        None => "src/input.txt",
+    };
 
     let file = File::open(filename).unwrap();
     let reader = BufReader::new(file);
@@ -266,20 +250,66 @@ fn main() {
     let data : Vec<String> = reader.lines()
         .collect::<Result<_, _>>().unwrap();
 
-    let mut scenario : Scenario = Scenario::new(0);
+    let joltage_after = |flag : &str, default : Jolts| -> Jolts {
+        match args.iter().position(|arg| arg == flag) {
+            Some(index) => args.get(index + 1).and_then(|arg| parse_joltage(arg)).unwrap_or_else(|| panic!("Expected a joltage of at least 0 after '{}'", flag)),
+            None => default,
+        }
+    };
+    let tolerance_after = |flag : &str| -> Jolts {
+        match args.iter().position(|arg| arg == flag) {
+            Some(index) => args.get(index + 1).and_then(|arg| parse_tolerance(arg)).unwrap_or_else(|| panic!("Expected a positive number after '{}'", flag)),
+            None => DEFAULT_TOLERANCE,
+        }
+    };
+    let tolerance : Jolts = tolerance_after("--tolerance");
+    let device_offset : Jolts = joltage_after("--device-offset", DEFAULT_DEVICE_OFFSET);
+    let device_tolerance : Jolts = tolerance_after("--device-tolerance");
+    let outlets : Vec<Jolts> = match args.iter().position(|arg| arg == "--outlet") {
+        Some(index) => args.get(index + 1).expect("Missing joltages after '--outlet'")
+            .split(',')
+            .map(|value| parse_joltage(value.trim()).unwrap_or_else(|| panic!("Invalid outlet joltage '{}'", value)))
+            .collect(),
+        None => vec![DEFAULT_OUTLET],
+    };
+
+    let mut scenario : Scenario = Scenario::new(outlets, device_offset, device_tolerance);
+
+    for (index_line, line) in data.iter().enumerate() {
+        if line.trim().is_empty() { continue; }
 
-    for line in data.iter() {
-        let value : u32 = match line.parse() {
-            Ok(value) => value,
-            Err(e) => panic!("{}", e),
+        let (value, negative_tolerance) : (Jolts, Option<Jolts>) = match parse_adapter(line) {
+            Ok(adapter) => adapter,
+            Err(e) => panic!("Line {}: {}", index_line + 1, e),
         };
 
-        scenario.add_adapter(value as Jolts);
+        scenario.add_adapter(value, negative_tolerance.unwrap_or(tolerance));
     }
 
-    scenario.compute_device_output();
+    if let Err(e) = scenario.compute_device_output() { panic!("{}", e); }
     scenario.order_adapters();
 
+    // 'chains' and 'stats' come before the differences, which need every adapter to be used.
+    // 'chains' lists the first valid chains, as many as '--limit' asks for
+    if args.first().map(|arg| arg.as_str()) == Some("chains") {
+        let limit : usize = match args.iter().position(|arg| arg == "--limit") {
+            Some(index) => args.get(index + 1).and_then(|arg| arg.parse().ok()).expect("Expected a number after '--limit'"),
+            None => DEFAULT_CHAIN_LIMIT,
+        };
+
+        for (outlet, chain) in scenario.orderings().take(limit) {
+            let jolts : Vec<String> = chain.iter().map(|joltage| joltage.to_string()).collect();
+            println!("{} -> {} -> {}", outlet, jolts.join(" -> "), scenario.device_jolts);
+        }
+        return;
+    }
+
+    // 'stats' gives the shortest and longest chains and the joltage differences over every chain
+    if args.first().map(|arg| arg.as_str()) == Some("stats") {
+        stats::print_stats(&scenario);
+        return;
+    }
+
     let diffs : Vec<Differential> = match scenario.compute_differences() {
         Ok(diffs) => diffs,
         Err(e) => panic!("{}", e),
@@ -287,13 +317,6 @@ fn main() {
 
     for diff in diffs { println!("{}", diff); }
 
-    /* Too computationally intensive */
-    /*
-    let orders : Vec<Vec<Adapter>> = scenario.compute_orderings();
-    for ordering in orders.iter() { println!("{:?}", ordering); }
-    println!("Total number of available orders: {}", orders.len());
-    */
-
-    let number_orders : u64 = scenario.compute_number_orderings();
+    let number_orders : BigUint = scenario.compute_number_orderings();
     println!("Total number of available orders: {}", number_orders);
 }
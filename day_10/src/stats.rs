@@ -0,0 +1,102 @@
+use std::collections::BTreeMap;
+
+use num_bigint::BigUint;
+
+use crate::{Jolts, Scenario};
+
+// Fewest and most adapters used by a chain reaching each adapter, None when no chain reaches it
+fn chain_lengths(scenario : &Scenario) -> Vec<Option<(usize, usize)>> {
+    let mut lengths : Vec<Option<(usize, usize)>> = Vec::with_capacity(scenario.adapters.len());
+
+    for (index, adapter) in scenario.adapters.iter().enumerate() {
+        let mut length : Option<(usize, usize)> = if scenario.outlets_accepted(adapter) > 0 { Some((1, 1)) } else { None };
+
+        for previous in scenario.feeding_adapters(index) {
+            if let Some((shortest, longest)) = lengths[previous] {
+                length = Some(match length {
+                    Some((current_shortest, current_longest)) => (current_shortest.min(shortest + 1), current_longest.max(longest + 1)),
+                    None => (shortest + 1, longest + 1),
+                });
+            }
+        }
+
+        lengths.push(length);
+    }
+
+    lengths
+}
+
+// Shortest and longest number of adapters over the chains reaching the device
+pub fn chain_length_range(scenario : &Scenario) -> Option<(usize, usize)> {
+    scenario.adapters.iter().zip(chain_lengths(scenario))
+        .filter(|(adapter, _)| scenario.device_accepts_joltage(adapter.get_joltage()))
+        .filter_map(|(_, length)| length)
+        .reduce(|(shortest, longest), (other_shortest, other_longest)| (shortest.min(other_shortest), longest.max(other_longest)))
+}
+
+// Number of chains going from each adapter to the device, the mirror of `count_chains_to_adapters`
+fn count_chains_from_adapters(scenario : &Scenario) -> Vec<BigUint> {
+    let adapters = &scenario.adapters;
+    let max_tolerance : Jolts = adapters.iter().map(|adapter| adapter.get_negative_tolerance()).max().unwrap_or(0);
+    let mut counts : Vec<BigUint> = vec![BigUint::from(0u32); adapters.len()];
+
+    for index in (0..adapters.len()).rev() {
+        let joltage : Jolts = adapters[index].get_joltage();
+        let mut count : BigUint = BigUint::from(scenario.device_accepts_joltage(joltage) as u32);
+
+        for next in (index + 1)..adapters.len() {
+            if adapters[next].get_joltage().checked_sub(joltage).is_none_or(|difference| difference > max_tolerance) { break; }
+            if adapters[next].accepts_joltage(joltage) { count += &counts[next]; }
+        }
+
+        counts[index] = count;
+    }
+
+    counts
+}
+
+// How many times each joltage difference shows up when adding up every valid chain,
+// each step between two points is used by all the chains reaching the first times all the ones leaving the second
+pub fn difference_distribution(scenario : &Scenario) -> BTreeMap<Jolts, BigUint> {
+    let to_adapters : Vec<BigUint> = scenario.count_chains_to_adapters();
+    let from_adapters : Vec<BigUint> = count_chains_from_adapters(scenario);
+    let mut distribution : BTreeMap<Jolts, BigUint> = BTreeMap::new();
+
+    for (index, adapter) in scenario.adapters.iter().enumerate() {
+        let joltage : Jolts = adapter.get_joltage();
+
+        // Accepted joltages are never above the adapter or the device, so the differences can't overflow
+        for &outlet in scenario.outlets.iter().filter(|&&outlet| adapter.accepts_joltage(outlet)) {
+            if let Some(difference) = joltage.checked_sub(outlet) { *distribution.entry(difference).or_default() += &from_adapters[index]; }
+        }
+        for previous in scenario.feeding_adapters(index) {
+            if let Some(difference) = joltage.checked_sub(scenario.adapters[previous].get_joltage()) {
+                *distribution.entry(difference).or_default() += &to_adapters[previous] * &from_adapters[index];
+            }
+        }
+        if scenario.device_accepts_joltage(joltage) {
+            if let Some(difference) = scenario.device_jolts.checked_sub(joltage) { *distribution.entry(difference).or_default() += &to_adapters[index]; }
+        }
+    }
+
+    distribution.retain(|_, count| *count != BigUint::from(0u32));
+    distribution
+}
+
+pub fn print_stats(scenario : &Scenario) {
+    println!("Outlets: {:?}, device: {} jolts", scenario.outlets, scenario.device_jolts);
+    println!("Total number of available orders: {}", scenario.compute_number_orderings());
+
+    match chain_length_range(scenario) {
+        Some((shortest, longest)) => {
+            println!("Fewest adapters in a chain: {}", shortest);
+            println!("Most adapters in a chain: {}", longest);
+        },
+        None => println!("No chain reaches the device"),
+    }
+
+    println!("Joltage differences over every chain:");
+    for (difference, count) in difference_distribution(scenario).iter() {
+        println!("\tDifferential of '{}' appears {} time(s)", difference, count);
+    }
+}
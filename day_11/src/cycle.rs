@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{Scenario, SeatState};
+use crate::rules::Rule;
+
+pub const DEFAULT_MAX_ITERATIONS : u32 = 10_000;
+
+// How a simulation ended, generation 0 being the starting layout
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    // The layout stopped changing from this generation on
+    Stabilised { generation : u32 },
+    // The layouts from `first_repeated` on repeat every `period` generations
+    Oscillated { period : u32, first_repeated : u32 },
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Stabilised { generation } => write!(f, "Stabilised at generation {}", generation),
+            Outcome::Oscillated { period, first_repeated } => write!(f, "Oscillates with period {} from generation {}", period, first_repeated),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SimulationError {
+    // No generation repeated within the given number of iterations
+    TooManyIterations(u32),
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SimulationError::TooManyIterations(iterations) => write!(f, "No repeated layout after {} iterations", iterations),
+        }
+    }
+}
+
+// Runs the rule until a layout shows up a second time, a stable layout being a cycle of period 1.
+// Layouts are looked up by hash and then compared seat by seat, so a collision can't fake a repeat.
+// The scenario is left on the repeated layout.
+pub fn simulate(scenario : &mut Scenario, rule : &Rule, max_iterations : u32) -> Result<Outcome, SimulationError> {
+    let mut seen : HashMap<Vec<SeatState>, u32> = HashMap::new();
+    seen.insert(scenario.get_layout(), 0);
+
+    for generation in 1..=max_iterations {
+        if let Err(e) = scenario.run_iteration(rule) { println!("{}", e); }
+
+        let layout : Vec<SeatState> = scenario.get_layout();
+        if let Some(&first_repeated) = seen.get(&layout) {
+            return Ok(match generation - first_repeated {
+                1 => Outcome::Stabilised { generation : first_repeated },
+                period => Outcome::Oscillated { period, first_repeated },
+            });
+        }
+        seen.insert(layout, generation);
+    }
+
+    Err(SimulationError::TooManyIterations(max_iterations))
+}
@@ -1,17 +1,20 @@
+// The original solution predates these lints and is kept as written
+#![allow(clippy::assign_op_pattern, clippy::clone_on_copy, clippy::needless_return, clippy::redundant_field_names, clippy::single_match, clippy::useless_conversion)]
+
+use std::env;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 
-type SeatNumber = i32;
+mod cycle;
+mod rules;
 
-// ------------------ Rules for Challenge ------------------
-#[derive(Copy, Clone)]
-enum Rule {
-    ImmediatelyNext,
-    FirstVisible,
-}
+use cycle::Outcome;
+use rules::Rule;
+
+type SeatNumber = i32;
 
 // ------------------ Seat State ------------------
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
 enum SeatState {
     Floor,
     Free,
@@ -127,6 +130,11 @@ impl Scenario {
         return counter;
     }
 
+    // Only the states change between generations, the shape of the rows never does
+    fn get_layout(&self) -> Vec<SeatState> {
+        self.seats.iter().flat_map(|row| row.iter().map(|seat| seat.get_state())).collect()
+    }
+
     fn get_seat_at(&self, row_number : SeatNumber, collumn_number : SeatNumber) -> Option<&Seat> {
         let number_rows : SeatNumber = self.seats.len() as SeatNumber;
         if row_number < 0 || row_number >= number_rows { return None; }
@@ -138,56 +146,54 @@ impl Scenario {
         return Some(&self.seats[row_number as usize][collumn_number as usize]);
     }
 
-    fn compute_number_occupied_by_rule(&self, current_row : SeatNumber, current_collumn : SeatNumber, rule : Rule) -> u32 {
+    // Seat at the position, coming back from the opposite edge when wrapping
+    fn get_seat_wrapping(&self, row_number : SeatNumber, collumn_number : SeatNumber, wrap : bool) -> Option<&Seat> {
+        if !wrap { return self.get_seat_at(row_number, collumn_number); }
+
+        let number_rows : SeatNumber = self.seats.len() as SeatNumber;
+        if number_rows == 0 { return None; }
+        let row_number : SeatNumber = row_number.rem_euclid(number_rows);
+
+        let number_collumns : SeatNumber = self.seats[row_number as usize].len() as SeatNumber;
+        if number_collumns == 0 { return None; }
+        self.get_seat_at(row_number, collumn_number.rem_euclid(number_collumns))
+    }
+
+    fn compute_number_occupied_by_rule(&self, current_row : SeatNumber, current_collumn : SeatNumber, rule : &Rule) -> u32 {
         let mut count_occupied : u32 = 0;
 
-        match rule {
-            Rule::ImmediatelyNext => {
-                for index_row in -1..2 {
-                    for index_collumn in -1..2 {
-                        if index_row == 0 && index_collumn == 0 { continue; }
-                        let next_to_seat : Option<&Seat> = self.get_seat_at(current_row + index_row, current_collumn + index_collumn);
-
-                        match next_to_seat {
-                            Some(next_to_seat) => {
-                                match next_to_seat.get_state() {
-                                    SeatState::Occupied => count_occupied = count_occupied + 1,
-                                    _ => ()
-                                }
-                            },
-                            None => ()
-                        }
-                    }
-                }
-            },
-
-            Rule::FirstVisible => {
-                for permutation_row in -1..2 {
-                    for permutation_collumn in -1..2 {
-                        if permutation_row == 0 && permutation_collumn == 0 { continue; }
-
-                        let permutation : (SeatNumber, SeatNumber) = (permutation_row, permutation_collumn);
-                        let mut at_point : (SeatNumber, SeatNumber) = (current_row, current_collumn);
-
-                        loop  {
-                            at_point = (at_point.0 + permutation.0, at_point.1 + permutation.1);
-                            let next_to_seat : Option<&Seat> = self.get_seat_at(at_point.0, at_point.1);
-
-                            match next_to_seat {
-                                Some(next_to_seat) => {
-                                    match next_to_seat.get_state() {
-                                        SeatState::Occupied => {
-                                            count_occupied = count_occupied + 1;
-                                            break;
-                                        },
-                                        SeatState::Free => break,
-                                        SeatState::Floor => ()
-                                    }
-                                },
-                                None => break
-                            }
-                        }
-                    }
+        // Wrapping rays without a range would go around forever, a ray can't see more cells than the layout has
+        let number_cells : SeatNumber = self.seats.iter().map(|row| row.len() as SeatNumber).sum();
+        let range : Option<SeatNumber> = match rule.get_range() {
+            None if rule.wrap => Some(number_cells),
+            range => range,
+        };
+
+        for &permutation in rule.get_directions().iter() {
+            let mut at_point : (SeatNumber, SeatNumber) = (current_row, current_collumn);
+            let mut distance : SeatNumber = 0;
+
+            loop {
+                distance += 1;
+                if range.map(|range| distance > range).unwrap_or(false) { break; }
+
+                at_point = (at_point.0 + permutation.0, at_point.1 + permutation.1);
+                let next_to_seat : &Seat = match self.get_seat_wrapping(at_point.0, at_point.1, rule.wrap) {
+                    Some(next_to_seat) => next_to_seat,
+                    None => break,
+                };
+
+                // Went all the way around back to the seat itself
+                if next_to_seat.get_row() == current_row && next_to_seat.get_collumn() == current_collumn { break; }
+
+                match next_to_seat.get_state() {
+                    SeatState::Occupied => {
+                        count_occupied = count_occupied + 1;
+                        break;
+                    },
+                    SeatState::Free => break,
+                    SeatState::Floor if rule.sees_through_floor() => (),
+                    SeatState::Floor => break,
                 }
             }
         }
@@ -195,7 +201,7 @@ impl Scenario {
         return count_occupied;
     }
 
-    fn run_iteration(&mut self, rule : Rule) -> Result<&'static str, &'static str> {
+    fn run_iteration(&mut self, rule : &Rule) -> Result<&'static str, &'static str> {
         let mut none_changed : bool = true;
         let mut next_states : Vec<Vec<Option<SeatState>>> = Vec::new();
 
@@ -210,15 +216,10 @@ impl Scenario {
                 let count_occupied : u32 = self.compute_number_occupied_by_rule(current_row, current_collumn, rule);
 
                 let next_state : Option<SeatState> = match seat.get_state() {
-                    SeatState::Free if count_occupied == 0 => Some(SeatState::Occupied),
+                    SeatState::Free if count_occupied <= rule.occupy_at_most => Some(SeatState::Occupied),
                     SeatState::Free => None,
-                    SeatState::Occupied => {
-                        match rule {
-                            Rule::ImmediatelyNext if count_occupied >= 4 => Some(SeatState::Free),
-                            Rule::FirstVisible if count_occupied >= 5 => Some(SeatState::Free),
-                            _ => None,
-                        }
-                    },
+                    SeatState::Occupied if count_occupied >= rule.free_at_least => Some(SeatState::Free),
+                    SeatState::Occupied => None,
                     SeatState::Floor => None,
                 };
 
@@ -253,7 +254,23 @@ impl Scenario {
 }
 
 fn main() {
-    let filename = "src/input.txt";
+    let args : Vec<String> = env::args().skip(1).collect();
+    let filename : &str = match args.iter().position(|arg| arg == "--input") {
+        Some(index) => args.get(index + 1).expect("Missing file after '--input'"),
+        None => "src/input.txt",
+    };
+    let print : bool = args.iter().any(|arg| arg == "--print");
+    let max_iterations : u32 = match args.iter().position(|arg| arg == "--max-iterations") {
+        Some(index) => args.get(index + 1).and_then(|arg| arg.parse().ok()).expect("Expected a number after '--max-iterations'"),
+        None => cycle::DEFAULT_MAX_ITERATIONS,
+    };
+
+    // Rules from the file are added to the presets, so both can be picked with '--rule'
+    let mut all_rules : Vec<Rule> = rules::presets();
+    if let Some(index) = args.iter().position(|arg| arg == "--rules") {
+        let rules_filename = args.get(index + 1).expect("Missing file after '--rules'");
+        all_rules.extend(rules::load(rules_filename).unwrap_or_else(|error| panic!("{}", error)));
+    }
 
     let file = File::open(filename).unwrap();
     let reader = BufReader::new(file);
@@ -270,31 +287,40 @@ fn main() {
         }
     }
 
-    //scenario.print_scenario();
+    if print { scenario.print_scenario(); }
     println!("At the beggining there are '{}' occupied seats.", scenario.get_number_occupied_seats());
-    
+
+    if let Some(index) = args.iter().position(|arg| arg == "--rule") {
+        let name = args.get(index + 1).expect("Missing name after '--rule'");
+        let rule : &Rule = rules::find(&all_rules, name).unwrap_or_else(|error| panic!("{}", error));
+
+        match cycle::simulate(&mut scenario, rule, max_iterations) {
+            Ok(outcome) => println!("{}", outcome),
+            Err(error) => println!("{}", error),
+        }
+        if print { scenario.print_scenario(); }
+        println!("At the end there are '{}' occupied seats.", scenario.get_number_occupied_seats());
+        return;
+    }
+
     // --------------------- Part 1 ---------------------
-    loop {
-        match scenario.run_iteration(Rule::ImmediatelyNext) {
-            Ok("Nothing changed!") => break,
-            Ok(_) => (),
-            Err(e) => println!("{}", e),
-        };
+    match cycle::simulate(&mut scenario, rules::find(&all_rules, "immediately_next").unwrap(), max_iterations) {
+        Ok(Outcome::Stabilised { .. }) => (),
+        Ok(outcome) => println!("{}", outcome),
+        Err(error) => println!("{}", error),
     }
-    //scenario.print_scenario();
+    if print { scenario.print_scenario(); }
     println!("At the end there are '{}' occupied seats.", scenario.get_number_occupied_seats());
 
     scenario.reset_seats_to_free();
 
     // --------------------- Part 2 ---------------------
-    loop {
-        match scenario.run_iteration(Rule::FirstVisible) {
-            Ok("Nothing changed!") => break,
-            Ok(_) => (),
-            Err(e) => println!("{}", e),
-        };
+    match cycle::simulate(&mut scenario, rules::find(&all_rules, "first_visible").unwrap(), max_iterations) {
+        Ok(Outcome::Stabilised { .. }) => (),
+        Ok(outcome) => println!("{}", outcome),
+        Err(error) => println!("{}", error),
     }
-    //scenario.print_scenario();
+    if print { scenario.print_scenario(); }
     println!("At the end there are '{}' occupied seats.", scenario.get_number_occupied_seats());
 
 }
@@ -0,0 +1,92 @@
+use std::fs;
+
+use serde::Deserialize;
+
+use crate::SeatNumber;
+
+const PRESET_RULES : &str = include_str!("rules.toml");
+
+const COMPASS : [(SeatNumber, SeatNumber); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+const KNIGHT : [(SeatNumber, SeatNumber); 8] = [(-2, -1), (-2, 1), (-1, -2), (-1, 2), (1, -2), (1, 2), (2, -1), (2, 1)];
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Neighbourhood {
+    // The 8 seats around
+    Adjacent,
+    // First seat seen in each of the 8 directions, skipping the floor, up to `range` cells away
+    LineOfSight { range : Option<SeatNumber> },
+    // The 8 seats a chess knight could jump to
+    Knight,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rule {
+    pub name : String,
+    pub occupy_at_most : u32,
+    pub free_at_least : u32,
+    pub neighbourhood : Neighbourhood,
+    // Leaving one edge of the layout comes back from the opposite one
+    #[serde(default)]
+    pub wrap : bool,
+}
+
+impl Rule {
+    pub fn get_directions(&self) -> &'static [(SeatNumber, SeatNumber)] {
+        match self.neighbourhood {
+            Neighbourhood::Knight => &KNIGHT,
+            _ => &COMPASS,
+        }
+    }
+
+    // Cells to walk in each direction, None to keep going until a seat or the edge
+    pub fn get_range(&self) -> Option<SeatNumber> {
+        match self.neighbourhood {
+            Neighbourhood::LineOfSight { range } => range,
+            _ => Some(1),
+        }
+    }
+
+    pub fn sees_through_floor(&self) -> bool {
+        matches!(self.neighbourhood, Neighbourhood::LineOfSight { .. })
+    }
+}
+
+#[derive(Deserialize)]
+struct RuleFile {
+    rules : Vec<Rule>,
+}
+
+pub fn from_toml(content : &str) -> Result<Vec<Rule>, String> {
+    let file : RuleFile = toml::from_str(content).map_err(|error| format!("Invalid TOML rules: {}", error))?;
+
+    for (index, rule) in file.rules.iter().enumerate() {
+        if file.rules[..index].iter().any(|other| other.name == rule.name) {
+            return Err(format!("Rule '{}' is defined more than once", rule.name));
+        }
+        if let Neighbourhood::LineOfSight { range : Some(range) } = rule.neighbourhood {
+            if range < 1 { return Err(format!("Rule '{}' should see at least 1 cell away", rule.name)); }
+        }
+    }
+
+    Ok(file.rules)
+}
+
+pub fn load(filename : &str) -> Result<Vec<Rule>, String> {
+    let content = fs::read_to_string(filename)
+        .map_err(|error| format!("Could not read rules '{}': {}", filename, error))?;
+
+    from_toml(&content)
+}
+
+pub fn presets() -> Vec<Rule> {
+    from_toml(PRESET_RULES).expect("Preset rules should be valid")
+}
+
+// The last rule with the name wins, so a loaded file can override a preset
+pub fn find<'a>(rules : &'a [Rule], name : &str) -> Result<&'a Rule, String> {
+    rules.iter().rev().find(|rule| rule.name == name).ok_or_else(|| {
+        let names : Vec<&str> = rules.iter().map(|rule| rule.name.as_str()).collect();
+        format!("Unknown rule '{}' (expected one of: {})", name, names.join(", "))
+    })
+}